@@ -11,6 +11,7 @@ use futures::executor;
 use lazy_static::lazy_static;
 
 #[derive(Clone, Copy, Debug)]
+#[allow(dead_code)]
 struct TaskId(usize);
 
 type Task = async_task::Task<TaskId>;
@@ -18,7 +19,7 @@ type JoinHandle<T> = async_task::JoinHandle<T, TaskId>;
 
 thread_local! {
     /// The ID of the current task.
-    static TASK_ID: Cell<Option<TaskId>> = Cell::new(None);
+    static TASK_ID: Cell<Option<TaskId>> = const { Cell::new(None) };
 }
 
 /// Returns the ID of the currently executing task.
@@ -0,0 +1,112 @@
+use core::cell::RefCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+
+use std::thread_local;
+use std::time::Instant;
+
+use crate::parking::Parker;
+use crate::Elapsed;
+
+thread_local! {
+    /// Parker and waker associated with the current thread.
+    static CACHE: RefCell<(Parker, Waker)> = RefCell::new(parker_and_waker());
+}
+
+/// Creates a parker for the current thread and a waker that unparks it.
+fn parker_and_waker() -> (Parker, Waker) {
+    let parker = Parker::new();
+    let waker = parker.unparker().into_waker();
+    (parker, waker)
+}
+
+/// Runs a future to completion on the current thread.
+///
+/// The thread is blocked while the future is pending and gets unblocked when the future is woken.
+/// The parker and waker are cached in a thread-local, so calling this function does not allocate
+/// except on the first call on each thread. Nested calls are allowed and use a parker of their
+/// own.
+///
+/// **NOTE:** This function is only available when the `std` feature for this crate is enabled (it
+/// is by default).
+///
+/// # Examples
+///
+/// ```
+/// let (task, handle) = async_task::spawn(async { 1 + 2 }, |_| {}, ());
+/// task.run();
+///
+/// assert_eq!(async_task::block_on(handle), Some(3));
+/// ```
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    match run(future, None) {
+        Ok(output) => output,
+        Err(_) => unreachable!("`block_on()` without a deadline timed out"),
+    }
+}
+
+/// Runs a future to completion on the current thread, or until `timeout` elapses.
+///
+/// Returns `Err(Elapsed)` if the future does not complete in time, in which case the future is
+/// dropped. Otherwise, this function works just like [`block_on()`].
+///
+/// **NOTE:** This function is only available when the `std` feature for this crate is enabled (it
+/// is by default).
+///
+/// [`block_on()`]: fn.block_on.html
+///
+/// # Examples
+///
+/// ```
+/// use futures::future;
+/// use std::time::Duration;
+///
+/// let res = async_task::block_on_timeout(future::pending::<()>(), Duration::from_millis(10));
+/// assert!(res.is_err());
+/// ```
+pub fn block_on_timeout<F: Future>(future: F, timeout: Duration) -> Result<F::Output, Elapsed> {
+    run(future, Instant::now().checked_add(timeout))
+}
+
+/// Runs a future until it completes or `deadline` is reached.
+fn run<F: Future>(future: F, deadline: Option<Instant>) -> Result<F::Output, Elapsed> {
+    // Pin the future on the stack. It is shadowed so that it can't be moved afterwards.
+    let mut future = future;
+    let mut future = unsafe { Pin::new_unchecked(&mut future) };
+
+    CACHE.with(|cache| {
+        // Use the cached parker and waker, unless this is a nested call that is already using
+        // them, in which case create new ones.
+        let cached;
+        let fresh;
+        let (parker, waker) = match cache.try_borrow_mut() {
+            Ok(c) => {
+                cached = c;
+                (&cached.0, &cached.1)
+            }
+            Err(_) => {
+                fresh = parker_and_waker();
+                (&fresh.0, &fresh.1)
+            }
+        };
+
+        let cx = &mut Context::from_waker(waker);
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(cx) {
+                return Ok(output);
+            }
+
+            match deadline {
+                None => parker.park(),
+                Some(deadline) => {
+                    if !parker.park_deadline(deadline) {
+                        return Err(Elapsed::new());
+                    }
+                }
+            }
+        }
+    })
+}
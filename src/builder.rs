@@ -0,0 +1,220 @@
+use core::future::Future;
+use core::marker::PhantomData;
+use core::mem;
+
+use crate::name::TaskName;
+use crate::raw::RawTask;
+use crate::{JoinHandle, Task};
+
+#[cfg(feature = "std")]
+use crate::LocalMisuse;
+
+/// Creates tasks with custom configuration.
+///
+/// [`spawn()`] and [`spawn_local()`] are shorthands for spawning with a default builder. A builder
+/// can additionally give the task a [name].
+///
+/// [`spawn()`]: fn.spawn.html
+/// [`spawn_local()`]: fn.spawn_local.html
+/// [name]: struct.TaskName.html
+///
+/// # Examples
+///
+/// ```
+/// use async_task::Builder;
+/// use crossbeam::channel;
+///
+/// let (s, r) = channel::unbounded();
+/// let schedule = move |task| s.send(task).unwrap();
+///
+/// let (task, handle) = Builder::new()
+///     .name("greeter")
+///     .spawn(async { println!("Hello, world!") }, schedule, ());
+///
+/// assert_eq!(handle.name(), Some("greeter"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Builder {
+    /// The name given to the task.
+    name: Option<TaskName>,
+}
+
+impl Builder {
+    /// Creates a builder with the default configuration.
+    pub fn new() -> Builder {
+        Builder::default()
+    }
+
+    /// Sets the name of the task.
+    ///
+    /// Tasks are unnamed by default.
+    pub fn name(mut self, name: impl Into<TaskName>) -> Builder {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Creates a new task.
+    ///
+    /// This is like [`spawn()`], but configured by the builder.
+    ///
+    /// [`spawn()`]: fn.spawn.html
+    #[track_caller]
+    pub fn spawn<F, R, S, T>(self, future: F, schedule: S, tag: T) -> (Task<T>, JoinHandle<R, T>)
+    where
+        F: Future<Output = R> + Send + 'static,
+        R: Send + 'static,
+        S: Fn(Task<T>) + Send + Sync + 'static,
+        T: Send + Sync + 'static,
+    {
+        // Allocate large futures on the heap.
+        let raw_task = if mem::size_of::<F>() >= 2048 {
+            let future = alloc::boxed::Box::pin(future);
+            RawTask::<_, R, S, T>::allocate(future, schedule, tag, self.name)
+        } else {
+            RawTask::<F, R, S, T>::allocate(future, schedule, tag, self.name)
+        };
+
+        let task = Task {
+            raw_task,
+            _marker: PhantomData,
+        };
+        let handle = JoinHandle {
+            raw_task,
+            _marker: PhantomData,
+        };
+        (task, handle)
+    }
+
+    /// Creates a new local task.
+    ///
+    /// This is like [`spawn_local()`], but configured by the builder. If the task is named, its
+    /// name is included in the panic message when the [`Task`] reference is run or dropped on the
+    /// wrong thread.
+    ///
+    /// **NOTE:** This method is only available when the `std` feature for this crate is enabled
+    /// (it is by default).
+    ///
+    /// [`spawn_local()`]: fn.spawn_local.html
+    /// [`Task`]: struct.Task.html
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn spawn_local<F, R, S, T>(
+        self,
+        future: F,
+        schedule: S,
+        tag: T,
+    ) -> (Task<T>, JoinHandle<R, T>)
+    where
+        F: Future<Output = R> + 'static,
+        R: 'static,
+        S: Fn(Task<T>) + Send + Sync + 'static,
+        T: Send + Sync + 'static,
+    {
+        extern crate std;
+
+        use std::mem::ManuallyDrop;
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+        use std::thread::ThreadId;
+
+        use crate::utils::thread_id;
+
+        struct Checked<F> {
+            id: ThreadId,
+            name: Option<TaskName>,
+            inner: ManuallyDrop<F>,
+        }
+
+        impl<F> Checked<F> {
+            /// Panics if the current thread is not the one that spawned the task.
+            fn check(&self, action: &str) {
+                if self.id != thread_id() {
+                    match &self.name {
+                        None => panic!("local task {} by a thread that didn't spawn it", action),
+                        Some(name) => panic!(
+                            "local task {:?} {} by a thread that didn't spawn it",
+                            name, action
+                        ),
+                    }
+                }
+            }
+        }
+
+        impl<F> Drop for Checked<F> {
+            fn drop(&mut self) {
+                self.check("dropped");
+                unsafe {
+                    ManuallyDrop::drop(&mut self.inner);
+                }
+            }
+        }
+
+        impl<F: Future> Future for Checked<F> {
+            type Output = F::Output;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                self.check("polled");
+                unsafe { self.map_unchecked_mut(|c| &mut *c.inner).poll(cx) }
+            }
+        }
+
+        // Wrap the future into one that which thread it's on.
+        let future = Checked {
+            id: thread_id(),
+            name: self.name.clone(),
+            inner: ManuallyDrop::new(future),
+        };
+
+        // Allocate large futures on the heap.
+        let raw_task = if mem::size_of::<F>() >= 2048 {
+            let future = alloc::boxed::Box::pin(future);
+            RawTask::<_, R, S, T>::allocate(future, schedule, tag, self.name)
+        } else {
+            RawTask::<_, R, S, T>::allocate(future, schedule, tag, self.name)
+        };
+
+        let task = Task {
+            raw_task,
+            _marker: PhantomData,
+        };
+        let handle = JoinHandle {
+            raw_task,
+            _marker: PhantomData,
+        };
+        (task, handle)
+    }
+
+    /// Creates a new local task with a policy for misuse on the wrong thread.
+    ///
+    /// This is like [`spawn_local_with_policy()`], but configured by the builder. If the task is
+    /// named, its name is included in the panic messages raised by the policy.
+    ///
+    /// **NOTE:** This method is only available when the `std` feature for this crate is enabled
+    /// (it is by default).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `policy` is [`LocalMisuse::SendBack`] and no [`Mailbox`] is registered on the
+    /// current thread.
+    ///
+    /// [`spawn_local_with_policy()`]: fn.spawn_local_with_policy.html
+    /// [`LocalMisuse::SendBack`]: enum.LocalMisuse.html#variant.SendBack
+    /// [`Mailbox`]: struct.Mailbox.html
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn spawn_local_with_policy<F, R, S, T>(
+        self,
+        future: F,
+        schedule: S,
+        tag: T,
+        policy: LocalMisuse,
+    ) -> (Task<T>, JoinHandle<R, T>)
+    where
+        F: Future<Output = R> + 'static,
+        R: 'static,
+        S: Fn(Task<T>) + Send + Sync + 'static,
+        T: Send + Sync + 'static,
+    {
+        crate::misuse::spawn(future, schedule, tag, policy, self.name)
+    }
+}
@@ -0,0 +1,348 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, Waker};
+
+use std::sync::Mutex;
+
+use crate::task_ref::TaskRef;
+use crate::{JoinHandle, Task};
+
+/// A token that cancels a group of tasks.
+///
+/// Tasks are attached to the token when spawned with [`spawn()`] or [`spawn_local()`], or
+/// afterwards with [`attach()`]. Triggering the token with [`cancel()`] cancels every attached
+/// task the same way [`JoinHandle::cancel()`] does. A future can also await [`cancelled()`] in
+/// order to clean up cooperatively before it gets dropped.
+///
+/// The token is cheap to clone, and all clones refer to the same token.
+///
+/// **NOTE:** This type is only available when the `std` feature for this crate is enabled (it is
+/// by default).
+///
+/// # Examples
+///
+/// ```
+/// use async_task::CancellationToken;
+/// use futures::future;
+///
+/// let token = CancellationToken::new();
+///
+/// let (task, handle) = token.spawn(future::pending::<()>(), |_| {}, ());
+///
+/// token.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+///
+/// [`spawn()`]: #method.spawn
+/// [`spawn_local()`]: #method.spawn_local
+/// [`attach()`]: #method.attach
+/// [`cancel()`]: #method.cancel
+/// [`cancelled()`]: #method.cancelled
+/// [`JoinHandle::cancel()`]: struct.JoinHandle.html#method.cancel
+#[derive(Clone)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    /// Set once the token has been triggered.
+    cancelled: AtomicBool,
+
+    /// Attached tasks and futures waiting for cancellation.
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Tasks canceled by the token, keyed by an ID.
+    tasks: Vec<(usize, TaskRef)>,
+
+    /// The number of tasks at which finished tasks attached with `attach()` are forgotten next.
+    prune_at: usize,
+
+    /// Wakers registered by `Cancelled` futures, keyed by an ID.
+    wakers: Vec<(usize, Waker)>,
+
+    /// The ID assigned to the next task or `Cancelled` future.
+    next_id: usize,
+}
+
+impl State {
+    /// Returns a new ID for a task or `Cancelled` future.
+    fn next_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        id
+    }
+}
+
+impl CancellationToken {
+    /// Creates a new token.
+    pub fn new() -> CancellationToken {
+        CancellationToken {
+            inner: Arc::new(Inner {
+                cancelled: AtomicBool::new(false),
+                state: Mutex::new(State::default()),
+            }),
+        }
+    }
+
+    /// Creates a new task attached to the token.
+    ///
+    /// This is like [`spawn()`], except that the task will be canceled when the token is
+    /// triggered. If the token has already been triggered, the task is canceled right away. The
+    /// token forgets the task as soon as its future completes or is dropped.
+    ///
+    /// [`spawn()`]: fn.spawn.html
+    #[track_caller]
+    pub fn spawn<F, R, S, T>(&self, future: F, schedule: S, tag: T) -> (Task<T>, JoinHandle<R, T>)
+    where
+        F: Future<Output = R> + Send + 'static,
+        R: Send + 'static,
+        S: Fn(Task<T>) + Send + Sync + 'static,
+        T: Send + Sync + 'static,
+    {
+        let future = self.wrap(future);
+        let id = future.id;
+        let (task, handle) = crate::spawn(future, schedule, tag);
+        self.insert(id, &handle);
+        (task, handle)
+    }
+
+    /// Creates a new local task attached to the token.
+    ///
+    /// This is like [`spawn_local()`], except that the task will be canceled when the token is
+    /// triggered. If the token has already been triggered, the task is canceled right away. The
+    /// token forgets the task as soon as its future completes or is dropped.
+    ///
+    /// [`spawn_local()`]: fn.spawn_local.html
+    #[track_caller]
+    pub fn spawn_local<F, R, S, T>(
+        &self,
+        future: F,
+        schedule: S,
+        tag: T,
+    ) -> (Task<T>, JoinHandle<R, T>)
+    where
+        F: Future<Output = R> + 'static,
+        R: 'static,
+        S: Fn(Task<T>) + Send + Sync + 'static,
+        T: Send + Sync + 'static,
+    {
+        let future = self.wrap(future);
+        let id = future.id;
+        let (task, handle) = crate::spawn_local(future, schedule, tag);
+        self.insert(id, &handle);
+        (task, handle)
+    }
+
+    /// Attaches a task to the token.
+    ///
+    /// The task will be canceled when the token is triggered. If the token has already been
+    /// triggered, the task is canceled right away.
+    ///
+    /// The token can't tell when a task attached this way finishes, so it only forgets finished
+    /// tasks from time to time as more tasks are attached, and keeps them allocated until then.
+    /// Tasks spawned with [`spawn()`] or [`spawn_local()`] are forgotten as soon as they finish.
+    ///
+    /// [`spawn()`]: #method.spawn
+    /// [`spawn_local()`]: #method.spawn_local
+    pub fn attach<R, T>(&self, handle: &JoinHandle<R, T>) {
+        let id = self.inner.state.lock().unwrap().next_id();
+        self.insert(id, handle);
+    }
+
+    /// Wraps a future so that it detaches itself from the token when dropped.
+    fn wrap<F>(&self, future: F) -> Attached<F> {
+        Attached {
+            inner: self.inner.clone(),
+            id: self.inner.state.lock().unwrap().next_id(),
+            future,
+        }
+    }
+
+    /// Attaches a task under the given ID, or cancels it if the token has been triggered.
+    fn insert<R, T>(&self, id: usize, handle: &JoinHandle<R, T>) {
+        let task = TaskRef::new(handle.raw_task);
+        let mut finished = Vec::new();
+
+        if !self.is_cancelled() {
+            let mut state = self.inner.state.lock().unwrap();
+
+            // Check again because the token might have been triggered in the meantime.
+            if !self.is_cancelled() {
+                state.tasks.push((id, task));
+
+                // Forget the tasks that can no longer be canceled once in a while, which keeps the
+                // cost of attaching constant on average.
+                if state.tasks.len() >= state.prune_at {
+                    let (done, live) = mem::take(&mut state.tasks)
+                        .into_iter()
+                        .partition(|(_, t)| t.is_finished());
+                    finished = done;
+                    state.tasks = live;
+                    state.prune_at = (state.tasks.len() * 2).max(16);
+                }
+                drop(state);
+
+                // Dropping a reference may drop the future of the task, which locks the state.
+                drop(finished);
+                return;
+            }
+        }
+
+        task.cancel();
+    }
+
+    /// Triggers the token.
+    ///
+    /// All attached tasks get canceled and all futures returned by [`cancelled()`] complete.
+    /// Triggering the token more than once has no effect.
+    ///
+    /// [`cancelled()`]: #method.cancelled
+    pub fn cancel(&self) {
+        let (tasks, wakers) = {
+            let mut state = self.inner.state.lock().unwrap();
+            if self.inner.cancelled.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            (mem::take(&mut state.tasks), mem::take(&mut state.wakers))
+        };
+
+        for (_, task) in &tasks {
+            task.cancel();
+        }
+        for (_, waker) in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Returns `true` if the token has been triggered.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Returns a future that completes when the token is triggered.
+    pub fn cancelled(&self) -> Cancelled<'_> {
+        Cancelled {
+            token: self,
+            id: None,
+        }
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> CancellationToken {
+        CancellationToken::new()
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Future returned by [`CancellationToken::cancelled()`].
+///
+/// [`CancellationToken::cancelled()`]: struct.CancellationToken.html#method.cancelled
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Cancelled<'a> {
+    /// The token being awaited.
+    token: &'a CancellationToken,
+
+    /// The ID of the registered waker.
+    id: Option<usize>,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        let mut state = self.token.inner.state.lock().unwrap();
+
+        // Check again because the token might have been triggered in the meantime.
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        match self.id {
+            Some(id) => {
+                if let Some((_, w)) = state.wakers.iter_mut().find(|(i, _)| *i == id) {
+                    if !w.will_wake(cx.waker()) {
+                        *w = cx.waker().clone();
+                    }
+                }
+            }
+            None => {
+                let id = state.next_id();
+                state.wakers.push((id, cx.waker().clone()));
+                drop(state);
+                self.id = Some(id);
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for Cancelled<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut state = self.token.inner.state.lock().unwrap();
+            state.wakers.retain(|(i, _)| *i != id);
+        }
+    }
+}
+
+impl fmt::Debug for Cancelled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cancelled")
+            .field("token", self.token)
+            .finish()
+    }
+}
+
+/// A future attached to a token, which detaches its task from the token when dropped.
+struct Attached<F> {
+    /// The token.
+    inner: Arc<Inner>,
+
+    /// The ID of the task in the token.
+    id: usize,
+
+    /// The inner future.
+    future: F,
+}
+
+impl<F: Future> Future for Attached<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        unsafe { self.map_unchecked_mut(|a| &mut a.future).poll(cx) }
+    }
+}
+
+impl<F> Drop for Attached<F> {
+    fn drop(&mut self) {
+        let task = {
+            let mut state = self.inner.state.lock().unwrap();
+            let index = state.tasks.iter().position(|(i, _)| *i == self.id);
+            index.map(|index| state.tasks.swap_remove(index))
+        };
+
+        // The future is dropped by the task itself, so this is never the last reference.
+        drop(task);
+    }
+}
@@ -0,0 +1,89 @@
+use core::cell::Cell;
+use core::ptr;
+use core::sync::atomic::Ordering;
+
+use std::thread_local;
+
+use crate::header::Header;
+use crate::state::*;
+
+thread_local! {
+    /// The header of the task whose future is currently being polled on this thread.
+    static CURRENT: Cell<*const Header> = const { Cell::new(ptr::null()) };
+}
+
+/// Restores the previously running task when dropped.
+pub(crate) struct Enter(*const Header);
+
+impl Drop for Enter {
+    fn drop(&mut self) {
+        let _ = CURRENT.try_with(|current| current.set(self.0));
+    }
+}
+
+/// Marks `header` as the task being polled on this thread until the returned guard is dropped.
+#[inline]
+pub(crate) fn enter(header: *const Header) -> Enter {
+    Enter(
+        CURRENT
+            .try_with(|current| current.replace(header))
+            .unwrap_or(ptr::null()),
+    )
+}
+
+/// Calls `f` with the header of the task being polled on this thread, if any.
+#[inline]
+pub(crate) fn with<R>(f: impl FnOnce(Option<&Header>) -> R) -> R {
+    let header = CURRENT
+        .try_with(|current| current.get())
+        .unwrap_or(ptr::null());
+
+    // The header is valid because the task is kept alive while its future is being polled.
+    f(unsafe { header.as_ref() })
+}
+
+/// Returns `true` if cancellation of the current task has been requested.
+///
+/// Cancellation is requested by [`JoinHandle::request_cancel()`]. A future can call this function
+/// while being polled and wind down gracefully, for example by flushing buffers or notifying
+/// peers, before the task gets canceled for real.
+///
+/// Returns `false` if called outside of a task.
+///
+/// **NOTE:** This function is only available when the `std` feature for this crate is enabled (it
+/// is by default).
+///
+/// [`JoinHandle::request_cancel()`]: struct.JoinHandle.html#method.request_cancel
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam::channel;
+/// use futures::executor::block_on;
+/// use futures::future;
+/// use std::task::Poll;
+///
+/// let (s, r) = channel::unbounded();
+/// let schedule = move |task| s.send(task).unwrap();
+///
+/// let future = future::poll_fn(|_| {
+///     if async_task::cancel_requested() {
+///         Poll::Ready("stopped")
+///     } else {
+///         Poll::Pending
+///     }
+/// });
+/// let (task, handle) = async_task::spawn(future, schedule, ());
+/// task.run();
+///
+/// // The task gets woken and its future observes the request.
+/// handle.request_cancel();
+/// r.recv().unwrap().run();
+/// assert_eq!(block_on(handle), Some("stopped"));
+/// ```
+pub fn cancel_requested() -> bool {
+    with(|header| match header {
+        None => false,
+        Some(header) => header.state.load(Ordering::Acquire) & CANCEL_REQUESTED != 0,
+    })
+}
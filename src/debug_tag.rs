@@ -0,0 +1,72 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::{type_name, TypeId};
+use core::fmt;
+
+use std::sync::Mutex;
+
+use crate::header::Header;
+
+/// Debug formatters of tag types registered with [`debug_tags()`].
+static FORMATTERS: Mutex<Vec<(TypeId, FmtTag)>> = Mutex::new(Vec::new());
+
+/// Formats a tag behind a pointer.
+type FmtTag = unsafe fn(*const (), &mut fmt::Formatter<'_>) -> fmt::Result;
+
+/// Formats the tag of type `T` behind a pointer, using the formatter registered for `T`.
+///
+/// Tags of types without a registered formatter are shown as their type name.
+pub(crate) unsafe fn fmt_tag<T: 'static>(tag: *const T, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let id = TypeId::of::<T>();
+    let formatter = FORMATTERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .find(|(t, _)| *t == id)
+        .map(|(_, fmt)| *fmt);
+
+    match formatter {
+        Some(fmt) => fmt(tag as *const (), f),
+        None => write!(f, "<{}>", type_name::<T>()),
+    }
+}
+
+/// Formats the tag of a task.
+///
+/// The task must stay allocated while its tag is being formatted.
+pub(crate) unsafe fn format(header: *const Header) -> String {
+    struct Tag(*const Header);
+
+    impl fmt::Display for Tag {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            unsafe { ((*self.0).vtable.fmt_tag)(self.0 as *const (), f) }
+        }
+    }
+
+    format!("{}", Tag(header))
+}
+
+/// Shows tags of type `T` using their [`Debug`] implementation in task dumps and reports.
+///
+/// Tags are not required to implement [`Debug`], so tags of types that weren't registered with
+/// this function are shown as their type name instead. Registered tags are shown by [`dump()`] and
+/// in [`SlowPoll`] reports.
+///
+/// **NOTE:** This function is only available when the `registry` or `watchdog` feature for this
+/// crate is enabled.
+///
+/// [`Debug`]: https://doc.rust-lang.org/std/fmt/trait.Debug.html
+/// [`dump()`]: fn.dump.html
+/// [`SlowPoll`]: struct.SlowPoll.html
+pub fn debug_tags<T: fmt::Debug + 'static>() {
+    unsafe fn fmt<T: fmt::Debug>(tag: *const (), f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*(tag as *const T), f)
+    }
+
+    let id = TypeId::of::<T>();
+    let mut formatters = FORMATTERS.lock().unwrap_or_else(|e| e.into_inner());
+    if formatters.iter().all(|(t, _)| *t != id) {
+        formatters.push((id, fmt::<T>));
+    }
+}
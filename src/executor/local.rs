@@ -0,0 +1,283 @@
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll};
+
+use std::cell::RefCell;
+use std::sync::Mutex;
+use std::thread::ThreadId;
+use std::thread_local;
+
+use crate::parking::{Parker, Unparker};
+use crate::utils::thread_id;
+use crate::{JoinHandle, LocalSpawn, Task};
+
+thread_local! {
+    /// Queues of dropped executors on this thread that can still receive tasks from other threads.
+    ///
+    /// Those tasks can only be canceled on this thread, which happens the next time an executor is
+    /// dropped here, or when the thread exits.
+    static ORPHANS: Orphans = const { Orphans(RefCell::new(Vec::new())) };
+}
+
+/// Queues of dropped executors, drained when the thread exits.
+struct Orphans(RefCell<Vec<Arc<Queue>>>);
+
+impl Orphans {
+    /// Cancels the tasks in the queues and forgets the queues that can't receive any more.
+    fn drain(&self) {
+        let mut queues = self.0.take();
+        for queue in &queues {
+            queue.drain();
+        }
+
+        // A queue is only referenced by its schedule functions and this list, so if it is the
+        // last reference, no task can be scheduled into it anymore.
+        queues.retain(|q| Arc::strong_count(q) > 1);
+        self.0.borrow_mut().extend(queues);
+    }
+}
+
+impl Drop for Orphans {
+    fn drop(&mut self) {
+        self.drain();
+    }
+}
+
+/// A single-threaded executor.
+///
+/// The executor can spawn futures that don't implement [`Send`] and runs them on the thread it
+/// was created on. Tasks are only run while the thread is inside one of the methods that drive
+/// the executor: [`run_until()`], [`tick()`], or [`try_tick()`].
+///
+/// If running a task panics, the panic is propagated into the caller of the method driving the
+/// executor. The panicking task is canceled, but the executor can still be used afterwards.
+///
+/// When the executor is dropped, its scheduled tasks are canceled, and so are tasks woken
+/// afterwards. Tasks woken on other threads can only be canceled on this thread, which happens
+/// when another executor is dropped on it or when the thread exits.
+///
+/// [`Send`]: https://doc.rust-lang.org/std/marker/trait.Send.html
+/// [`run_until()`]: #method.run_until
+/// [`tick()`]: #method.tick
+/// [`try_tick()`]: #method.try_tick
+///
+/// # Examples
+///
+/// ```
+/// use async_task::executor::LocalExecutor;
+/// use std::rc::Rc;
+///
+/// let ex = LocalExecutor::new();
+/// let val = Rc::new(7);
+///
+/// let handle = ex.spawn({
+///     let val = val.clone();
+///     async move { *val + 1 }
+/// });
+///
+/// assert_eq!(ex.run_until(handle), Some(8));
+/// ```
+pub struct LocalExecutor {
+    /// The queue of scheduled tasks.
+    queue: Arc<Queue>,
+
+    /// Blocks the thread while there is nothing to do.
+    parker: Parker,
+
+    /// Makes the type `!Send` and `!Sync`.
+    _marker: PhantomData<*mut ()>,
+}
+
+/// Scheduled tasks shared with the schedule functions.
+struct Queue {
+    /// Tasks ready to be run.
+    tasks: Mutex<VecDeque<Task<()>>>,
+
+    /// Set when the executor is dropped.
+    ///
+    /// It is only modified while the tasks are locked.
+    closed: AtomicBool,
+
+    /// The thread the executor was created on.
+    origin: ThreadId,
+
+    /// Wakes up the executor thread.
+    unparker: Unparker,
+}
+
+impl Queue {
+    /// Pushes a task into the queue and notifies the executor.
+    ///
+    /// Tasks scheduled after the executor is dropped are canceled if this is the origin thread.
+    /// Otherwise, they wait in the queue until the origin thread cancels them.
+    fn push(&self, task: Task<()>) {
+        let mut tasks = self.tasks.lock().unwrap();
+        if self.closed.load(Ordering::SeqCst) && thread_id() == self.origin {
+            drop(tasks);
+            drop(task);
+            return;
+        }
+        tasks.push_back(task);
+        drop(tasks);
+        self.unparker.unpark();
+    }
+
+    /// Takes the next task out of the queue.
+    fn pop(&self) -> Option<Task<()>> {
+        self.tasks.lock().unwrap().pop_front()
+    }
+
+    /// Cancels the scheduled tasks.
+    ///
+    /// This must be called on the origin thread, which is where their futures must be dropped.
+    fn drain(&self) {
+        while let Some(task) = self.pop() {
+            drop(task);
+        }
+    }
+}
+
+impl LocalExecutor {
+    /// Creates a new executor on the current thread.
+    pub fn new() -> LocalExecutor {
+        let parker = Parker::new();
+        LocalExecutor {
+            queue: Arc::new(Queue {
+                tasks: Mutex::new(VecDeque::new()),
+                closed: AtomicBool::new(false),
+                origin: thread_id(),
+                unparker: parker.unparker(),
+            }),
+            parker,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Spawns a future onto the executor.
+    ///
+    /// The future does not need to implement [`Send`] because it only ever runs on the executor's
+    /// thread.
+    ///
+    /// [`Send`]: https://doc.rust-lang.org/std/marker/trait.Send.html
+    #[track_caller]
+    pub fn spawn<F, R>(&self, future: F) -> JoinHandle<R, ()>
+    where
+        F: Future<Output = R> + 'static,
+        R: 'static,
+    {
+        let queue = self.queue.clone();
+        let schedule = move |task| queue.push(task);
+        let (task, handle) = crate::spawn_local(future, schedule, ());
+        task.schedule();
+        handle
+    }
+
+    /// Runs a single scheduled task, if there is one.
+    ///
+    /// Returns `true` if a task was run.
+    pub fn try_tick(&self) -> bool {
+        match self.queue.pop() {
+            None => false,
+            Some(task) => {
+                task.run();
+                true
+            }
+        }
+    }
+
+    /// Runs a single scheduled task, blocking until one is available.
+    pub fn tick(&self) {
+        while !self.try_tick() {
+            self.parker.park();
+        }
+    }
+
+    /// Runs the executor until `future` completes, and returns its output.
+    ///
+    /// Spawned tasks are run while waiting for the future. The thread is blocked when neither the
+    /// future nor any of the tasks can make progress.
+    pub fn run_until<F: Future>(&self, future: F) -> F::Output {
+        // Pin the future on the stack. It is shadowed so that it can't be moved afterwards.
+        let mut future = future;
+        let mut future = unsafe { Pin::new_unchecked(&mut future) };
+
+        // Set when the future is woken, and initially so that it gets polled right away.
+        let woken = Arc::new(AtomicBool::new(true));
+        let waker = {
+            let woken = woken.clone();
+            let unparker = self.queue.unparker.clone();
+            crate::waker_fn(move || {
+                woken.store(true, Ordering::SeqCst);
+                unparker.unpark();
+            })
+        };
+        let cx = &mut Context::from_waker(&waker);
+
+        loop {
+            if woken.swap(false, Ordering::SeqCst) {
+                if let Poll::Ready(output) = future.as_mut().poll(cx) {
+                    return output;
+                }
+            }
+
+            if !self.try_tick() && !woken.load(Ordering::SeqCst) {
+                self.parker.park();
+            }
+        }
+    }
+}
+
+impl LocalSpawn for LocalExecutor {
+    type Tag = ();
+
+    #[track_caller]
+    fn spawn_local<F>(&self, future: F) -> JoinHandle<F::Output, ()>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        self.spawn(future)
+    }
+}
+
+impl Default for LocalExecutor {
+    fn default() -> LocalExecutor {
+        LocalExecutor::new()
+    }
+}
+
+impl Drop for LocalExecutor {
+    fn drop(&mut self) {
+        // Tasks scheduled from now on are canceled instead of being queued.
+        {
+            let _tasks = self.queue.tasks.lock().unwrap();
+            self.queue.closed.store(true, Ordering::SeqCst);
+        }
+
+        // Cancel the scheduled tasks on this thread, which is where their futures must be dropped.
+        self.queue.drain();
+
+        // Tasks can still be woken on other threads, which can't cancel them. Keep the queue so
+        // that this thread cancels them later, rather than leaking them.
+        let _ = ORPHANS.try_with(|orphans| {
+            orphans.drain();
+            if Arc::strong_count(&self.queue) > 1 {
+                orphans.0.borrow_mut().push(self.queue.clone());
+            }
+        });
+    }
+}
+
+impl fmt::Debug for LocalExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalExecutor")
+            .field("scheduled", &self.queue.tasks.lock().unwrap().len())
+            .finish()
+    }
+}
@@ -0,0 +1,16 @@
+//! Reference executors built on top of this crate.
+//!
+//! These executors are small and simple rather than feature-rich. They are useful for tools and
+//! tests that need to run a few tasks, and as a starting point for building custom executors.
+//!
+//! **NOTE:** This module is only available when the `executor` feature for this crate is enabled.
+
+mod local;
+mod per_core;
+mod pool;
+mod priority;
+
+pub use self::local::LocalExecutor;
+pub use self::per_core::ThreadPerCore;
+pub use self::pool::{Builder, ThreadPool};
+pub use self::priority::{PriorityBuilder, PriorityExecutor};
@@ -0,0 +1,367 @@
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use core::task::{Context, Poll};
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Mutex};
+use std::thread::{self, ThreadId};
+
+use crate::parking::{Parker, Unparker};
+use crate::{JoinHandle, Spawn, Task};
+
+/// An executor with one thread per core and tasks pinned to cores.
+///
+/// Every task is bound to the core it was spawned on, and the index of that core is stored as its
+/// tag. Waking a task always pushes it into the inbox of its own core, even when it is woken from
+/// another thread, so a task is only ever polled and dropped by its core's thread.
+///
+/// Because of that guarantee, [`spawn_local_on()`] can run futures that don't implement [`Send`].
+/// Unlike [`spawn_local()`], which panics when a task is run on the wrong thread, affinity here is
+/// enforced by routing, so cross-thread wakes are safe.
+///
+/// Cores are logical: each one is a worker thread, which is not pinned to a particular CPU by the
+/// operating system. If running a task panics, the panic is caught and the task is canceled.
+///
+/// When the executor is shut down, the tasks that are still scheduled are canceled on their cores.
+/// Tasks spawned by [`spawn_on()`] that are woken after shutdown are canceled right away, while
+/// tasks spawned by [`spawn_local_on()`] are leaked because no thread is left that can drop them.
+/// A task may shut down the executor it runs on, in which case its own core stops after the task
+/// returns.
+///
+/// [`spawn_on()`]: #method.spawn_on
+/// [`spawn_local_on()`]: #method.spawn_local_on
+/// [`spawn_local()`]: ../fn.spawn_local.html
+/// [`Send`]: https://doc.rust-lang.org/std/marker/trait.Send.html
+///
+/// # Examples
+///
+/// ```
+/// use async_task::executor::ThreadPerCore;
+/// use futures::executor::block_on;
+/// use std::rc::Rc;
+///
+/// let ex = ThreadPerCore::new(2);
+///
+/// // The future is created on core 1 and never leaves it.
+/// let handle = ex.spawn_local_on(1, || {
+///     let val = Rc::new(7);
+///     async move { *val }
+/// });
+/// assert_eq!(block_on(handle), Some(7));
+/// ```
+pub struct ThreadPerCore {
+    /// State shared with the core threads and schedule functions.
+    shared: Arc<Shared>,
+
+    /// Handles of the core threads.
+    threads: Vec<thread::JoinHandle<()>>,
+
+    /// The core the next task spawned through [`Spawn`] goes to.
+    ///
+    /// [`Spawn`]: ../trait.Spawn.html
+    next: AtomicUsize,
+}
+
+/// State shared with the core threads and schedule functions.
+struct Shared {
+    /// The cores, indexed by their tag.
+    cores: Vec<Core>,
+
+    /// Set when the executor is shutting down.
+    shutdown: AtomicBool,
+}
+
+/// A core and its inbox.
+struct Core {
+    /// Tasks scheduled onto this core, from any thread.
+    inbox: Mutex<Inbox>,
+
+    /// Wakes up the core thread.
+    unparker: Unparker,
+}
+
+/// The inbox of a core.
+struct Inbox {
+    /// Tasks ready to be run.
+    tasks: VecDeque<Task<usize>>,
+
+    /// Set when the core thread has exited.
+    closed: bool,
+}
+
+impl Core {
+    /// Pushes a task into the inbox and notifies the core thread.
+    ///
+    /// If the core thread has exited, the task is given back.
+    fn push(&self, task: Task<usize>) -> Result<(), Task<usize>> {
+        let mut inbox = self.inbox.lock().unwrap();
+        if inbox.closed {
+            return Err(task);
+        }
+        inbox.tasks.push_back(task);
+        drop(inbox);
+
+        self.unparker.unpark();
+        Ok(())
+    }
+
+    /// Runs the main loop of the core thread.
+    fn run(&self, parker: &Parker, shutdown: &AtomicBool) {
+        loop {
+            let task = self.inbox.lock().unwrap().tasks.pop_front();
+
+            match task {
+                // Ignore panics because the task gets canceled anyway.
+                Some(task) => {
+                    let _ = panic::catch_unwind(AssertUnwindSafe(|| task.run()));
+                }
+                None if shutdown.load(Ordering::SeqCst) => break,
+                None => parker.park(),
+            }
+        }
+
+        // Cancel the remaining tasks on this thread, which is where their futures must be dropped.
+        let tasks = {
+            let mut inbox = self.inbox.lock().unwrap();
+            inbox.closed = true;
+            mem::take(&mut inbox.tasks)
+        };
+        drop(tasks);
+    }
+}
+
+impl ThreadPerCore {
+    /// Creates an executor with `num_cores` core threads.
+    ///
+    /// Core threads are named `async-task-core-{index}`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_cores` is zero or if a core thread could not be spawned.
+    pub fn new(num_cores: usize) -> ThreadPerCore {
+        assert!(
+            num_cores > 0,
+            "a thread-per-core executor needs at least one core"
+        );
+
+        let mut threads = Vec::with_capacity(num_cores);
+        let mut senders = Vec::with_capacity(num_cores);
+        let mut unparkers = Vec::with_capacity(num_cores);
+
+        for index in 0..num_cores {
+            // Each core thread creates its own parker, sends back the unparker, and then waits
+            // for the shared state, which can only be created once all unparkers exist.
+            let (unparker_s, unparker_r) = mpsc::channel();
+            let (shared_s, shared_r) = mpsc::channel::<Arc<Shared>>();
+
+            let handle = thread::Builder::new()
+                .name(format!("async-task-core-{}", index))
+                .spawn(move || {
+                    let parker = Parker::new();
+                    unparker_s.send(parker.unparker()).unwrap();
+
+                    if let Ok(shared) = shared_r.recv() {
+                        shared.cores[index].run(&parker, &shared.shutdown);
+                    }
+                })
+                .expect("cannot spawn core thread");
+
+            threads.push(handle);
+            senders.push(shared_s);
+            unparkers.push(unparker_r.recv().unwrap());
+        }
+
+        let shared = Arc::new(Shared {
+            cores: unparkers
+                .into_iter()
+                .map(|unparker| Core {
+                    inbox: Mutex::new(Inbox {
+                        tasks: VecDeque::new(),
+                        closed: false,
+                    }),
+                    unparker,
+                })
+                .collect(),
+            shutdown: AtomicBool::new(false),
+        });
+
+        for s in senders {
+            s.send(shared.clone()).unwrap();
+        }
+
+        ThreadPerCore {
+            shared,
+            threads,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the number of cores.
+    pub fn num_cores(&self) -> usize {
+        self.shared.cores.len()
+    }
+
+    /// Spawns a future onto core `core`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `core` is not less than the number of cores.
+    #[track_caller]
+    pub fn spawn_on<F, R>(&self, core: usize, future: F) -> JoinHandle<R, usize>
+    where
+        F: Future<Output = R> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.check(core);
+
+        let shared = self.shared.clone();
+        let schedule = move |task: Task<usize>| {
+            // The future is `Send`, so the task can be canceled on any thread.
+            let _ = shared.cores[*task.tag()].push(task);
+        };
+        let (task, handle) = crate::spawn(future, schedule, core);
+        task.schedule();
+        handle
+    }
+
+    /// Creates a future on core `core` and spawns it there.
+    ///
+    /// The future does not need to implement [`Send`] because it is created by calling `make` on
+    /// the core's thread, and is never polled or dropped by any other thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `core` is not less than the number of cores.
+    ///
+    /// [`Send`]: https://doc.rust-lang.org/std/marker/trait.Send.html
+    #[track_caller]
+    pub fn spawn_local_on<M, F, R>(&self, core: usize, make: M) -> JoinHandle<R, usize>
+    where
+        M: FnOnce() -> F + Send + 'static,
+        F: Future<Output = R> + 'static,
+        R: Send + 'static,
+    {
+        self.check(core);
+
+        let shared = self.shared.clone();
+        let schedule = move |task: Task<usize>| {
+            // The future must not be dropped on this thread, so leak it if its core has exited.
+            if let Err(task) = shared.cores[*task.tag()].push(task) {
+                mem::forget(task);
+            }
+        };
+        let future = Pinned {
+            make: Some(make),
+            future: None,
+            thread: None,
+        };
+        let (task, handle) = crate::spawn(future, schedule, core);
+        task.schedule();
+        handle
+    }
+
+    /// Stops the core threads and cancels all tasks that are still scheduled.
+    pub fn shutdown(self) {
+        drop(self);
+    }
+
+    /// Panics if `core` is out of range.
+    fn check(&self, core: usize) {
+        assert!(
+            core < self.shared.cores.len(),
+            "core {} is out of range for {} cores",
+            core,
+            self.shared.cores.len()
+        );
+    }
+}
+
+/// Spawns futures onto the cores in round-robin order.
+impl Spawn for ThreadPerCore {
+    type Tag = usize;
+
+    #[track_caller]
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output, usize>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let core = self.next.fetch_add(1, Ordering::Relaxed) % self.num_cores();
+        self.spawn_on(core, future)
+    }
+}
+
+impl Drop for ThreadPerCore {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+
+        for core in &self.shared.cores {
+            core.unparker.unpark();
+        }
+
+        // If the executor is dropped by one of its own tasks, the core thread running it can't be
+        // joined. It stops on its own once the task returns.
+        let current = thread::current().id();
+        for handle in self.threads.drain(..) {
+            if handle.thread().id() != current {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ThreadPerCore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadPerCore")
+            .field("num_cores", &self.num_cores())
+            .finish()
+    }
+}
+
+/// A future that is created and polled on the thread of its core.
+struct Pinned<M, F> {
+    /// Creates the future on first poll.
+    make: Option<M>,
+
+    /// The future, once created.
+    future: Option<F>,
+
+    /// The thread the future was created on.
+    thread: Option<ThreadId>,
+}
+
+// The future is created, polled, and dropped only by the thread of its core, because the
+// schedule function routes every wake into that core's inbox and never drops the task elsewhere.
+unsafe impl<M: Send, F> Send for Pinned<M, F> {}
+
+impl<M, F> Future for Pinned<M, F>
+where
+    M: FnOnce() -> F,
+    F: Future,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        // The future is never moved once created.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        if let Some(make) = this.make.take() {
+            this.future = Some(make());
+            this.thread = Some(thread::current().id());
+        }
+        debug_assert_eq!(this.thread, Some(thread::current().id()));
+
+        let future = this
+            .future
+            .as_mut()
+            .expect("future polled after completion");
+        unsafe { Pin::new_unchecked(future) }.poll(cx)
+    }
+}
@@ -0,0 +1,358 @@
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::fmt;
+use core::future::Future;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::thread;
+use std::thread_local;
+
+use crate::parking::{Parker, Unparker};
+use crate::{JoinHandle, Spawn, Task};
+
+thread_local! {
+    /// The pool and the index of the worker running on this thread.
+    static WORKER: Cell<(*const Shared, usize)> = const { Cell::new((ptr::null(), 0)) };
+}
+
+/// A multi-threaded executor with work stealing.
+///
+/// Every worker thread has its own queue. Tasks scheduled from a worker thread are pushed into
+/// that worker's queue, while tasks scheduled from other threads are pushed into a global
+/// injector queue. Idle workers steal tasks from the queues of other workers.
+///
+/// Workers sleep while there are no tasks to run. If running a task panics, the panic is caught
+/// and the task is canceled.
+///
+/// When the pool is shut down, either by [`shutdown()`] or by dropping it, the worker threads are
+/// stopped and the tasks that are still scheduled are canceled by dropping them. A task may shut
+/// down the pool it runs on, in which case its own worker stops after the task returns.
+///
+/// [`shutdown()`]: #method.shutdown
+///
+/// # Examples
+///
+/// ```
+/// use async_task::executor::ThreadPool;
+/// use futures::executor::block_on;
+///
+/// let pool = ThreadPool::builder().num_threads(2).name("worker").build().unwrap();
+/// let handle = pool.spawn(async { 1 + 2 });
+/// assert_eq!(block_on(handle), Some(3));
+/// ```
+pub struct ThreadPool {
+    /// State shared with the workers and schedule functions.
+    shared: Arc<Shared>,
+
+    /// Handles of the worker threads.
+    threads: Vec<thread::JoinHandle<()>>,
+}
+
+/// Builds a [`ThreadPool`] with custom configuration.
+///
+/// [`ThreadPool`]: struct.ThreadPool.html
+#[derive(Debug, Default)]
+pub struct Builder {
+    /// Number of worker threads.
+    num_threads: Option<usize>,
+
+    /// Prefix of the worker thread names.
+    name: Option<String>,
+}
+
+/// State shared with the workers and schedule functions.
+struct Shared {
+    /// Tasks scheduled from outside of the worker threads.
+    injector: Mutex<VecDeque<Task<()>>>,
+
+    /// Tasks scheduled by each of the workers.
+    locals: Vec<Mutex<VecDeque<Task<()>>>>,
+
+    /// Workers that are about to go to sleep, along with their indices.
+    sleepers: Mutex<Vec<(usize, Unparker)>>,
+
+    /// Set when the pool is shutting down.
+    shutdown: AtomicBool,
+}
+
+impl Builder {
+    /// Creates a builder with the default configuration.
+    pub fn new() -> Builder {
+        Builder::default()
+    }
+
+    /// Sets the number of worker threads.
+    ///
+    /// Defaults to the available parallelism of the system.
+    pub fn num_threads(mut self, num_threads: usize) -> Builder {
+        assert!(num_threads > 0, "a thread pool needs at least one thread");
+        self.num_threads = Some(num_threads);
+        self
+    }
+
+    /// Sets the name prefix of the worker threads.
+    ///
+    /// Worker threads are named `{name}-{index}`.
+    pub fn name(mut self, name: impl Into<String>) -> Builder {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Spawns the worker threads and returns the pool.
+    pub fn build(self) -> io::Result<ThreadPool> {
+        let num_threads = match self.num_threads {
+            Some(n) => n,
+            None => thread::available_parallelism().map_or(1, |n| n.get()),
+        };
+        let name = self.name.unwrap_or_else(|| String::from("async-task"));
+
+        let shared = Arc::new(Shared {
+            injector: Mutex::new(VecDeque::new()),
+            locals: (0..num_threads)
+                .map(|_| Mutex::new(VecDeque::new()))
+                .collect(),
+            sleepers: Mutex::new(Vec::new()),
+            shutdown: AtomicBool::new(false),
+        });
+
+        let mut pool = ThreadPool {
+            shared: shared.clone(),
+            threads: Vec::with_capacity(num_threads),
+        };
+
+        for index in 0..num_threads {
+            let shared = shared.clone();
+            let handle = thread::Builder::new()
+                .name(format!("{}-{}", name, index))
+                .spawn(move || shared.worker(index))?;
+            pool.threads.push(handle);
+        }
+
+        Ok(pool)
+    }
+}
+
+impl Shared {
+    /// Schedules a task.
+    fn schedule(self: &Arc<Self>, task: Task<()>) {
+        // Tasks scheduled after shutdown are canceled because no worker is left to run them.
+        if self.shutdown.load(Ordering::SeqCst) {
+            drop(task);
+            return;
+        }
+
+        let (pool, index) = WORKER.with(|w| w.get());
+        if pool == Arc::as_ptr(self) {
+            self.locals[index].lock().unwrap().push_back(task);
+        } else {
+            self.injector.lock().unwrap().push_back(task);
+        }
+
+        // The pool may have been shut down after the check above, and drained before the task was
+        // pushed. Check again so that the task doesn't stay in the queues forever.
+        if self.shutdown.load(Ordering::SeqCst) {
+            self.drain();
+            return;
+        }
+
+        // Wake up a sleeping worker, if there is one.
+        if let Some((_, unparker)) = self.sleepers.lock().unwrap().pop() {
+            unparker.unpark();
+        }
+    }
+
+    /// Finds the next task for worker `index` to run.
+    ///
+    /// If the previous task yielded, the injector is checked first so that tasks scheduled from
+    /// outside of the pool are not starved by tasks that keep rescheduling themselves.
+    fn find_task(&self, index: usize, yielded: bool) -> Option<Task<()>> {
+        if yielded {
+            if let Some(task) = self.injector.lock().unwrap().pop_front() {
+                return Some(task);
+            }
+        }
+
+        if let Some(task) = self.locals[index].lock().unwrap().pop_front() {
+            return Some(task);
+        }
+
+        if let Some(task) = self.injector.lock().unwrap().pop_front() {
+            return Some(task);
+        }
+
+        self.steal(index)
+    }
+
+    /// Steals half of the tasks from another worker.
+    fn steal(&self, index: usize) -> Option<Task<()>> {
+        let n = self.locals.len();
+
+        for i in 1..n {
+            let victim = (index + i) % n;
+            let mut stolen = {
+                let mut queue = self.locals[victim].lock().unwrap();
+                let count = queue.len().div_ceil(2);
+                let at = queue.len() - count;
+                queue.split_off(at)
+            };
+
+            if let Some(task) = stolen.pop_front() {
+                self.locals[index].lock().unwrap().extend(stolen);
+                return Some(task);
+            }
+        }
+
+        None
+    }
+
+    /// Runs the main loop of worker `index`.
+    fn worker(self: Arc<Self>, index: usize) {
+        WORKER.with(|w| w.set((Arc::as_ptr(&self), index)));
+        let parker = Parker::new();
+        let mut yielded = false;
+
+        while !self.shutdown.load(Ordering::SeqCst) {
+            if let Some(task) = self.find_task(index, yielded) {
+                // Ignore panics because the task gets canceled anyway.
+                yielded = panic::catch_unwind(AssertUnwindSafe(|| task.run())).unwrap_or(false);
+                continue;
+            }
+            yielded = false;
+
+            // Announce that this worker is going to sleep, then check once more for tasks
+            // scheduled in the meantime.
+            self.sleepers
+                .lock()
+                .unwrap()
+                .push((index, parker.unparker()));
+
+            if let Some(task) = self.find_task(index, false) {
+                self.sleepers.lock().unwrap().retain(|(i, _)| *i != index);
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| task.run()));
+                continue;
+            }
+
+            if !self.shutdown.load(Ordering::SeqCst) {
+                parker.park();
+            }
+        }
+
+        WORKER.with(|w| w.set((ptr::null(), 0)));
+    }
+
+    /// Cancels all scheduled tasks.
+    fn drain(&self) {
+        loop {
+            let task = self.injector.lock().unwrap().pop_front().or_else(|| {
+                self.locals
+                    .iter()
+                    .find_map(|queue| queue.lock().unwrap().pop_front())
+            });
+
+            match task {
+                None => break,
+                Some(task) => drop(task),
+            }
+        }
+    }
+}
+
+impl ThreadPool {
+    /// Creates a pool with the default configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a worker thread could not be spawned.
+    pub fn new() -> ThreadPool {
+        Builder::new()
+            .build()
+            .expect("cannot spawn thread pool workers")
+    }
+
+    /// Returns a builder for configuring a pool.
+    pub fn builder() -> Builder {
+        Builder::new()
+    }
+
+    /// Returns the number of worker threads.
+    pub fn num_threads(&self) -> usize {
+        self.shared.locals.len()
+    }
+
+    /// Spawns a future onto the pool.
+    #[track_caller]
+    pub fn spawn<F, R>(&self, future: F) -> JoinHandle<R, ()>
+    where
+        F: Future<Output = R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let shared = self.shared.clone();
+        let schedule = move |task| shared.schedule(task);
+        let (task, handle) = crate::spawn(future, schedule, ());
+        task.schedule();
+        handle
+    }
+
+    /// Stops the worker threads and cancels all tasks that are still scheduled.
+    ///
+    /// Tasks that are woken after shutdown are canceled right away.
+    pub fn shutdown(self) {
+        drop(self);
+    }
+}
+
+impl Spawn for ThreadPool {
+    type Tag = ();
+
+    #[track_caller]
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output, ()>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        ThreadPool::spawn(self, future)
+    }
+}
+
+impl Default for ThreadPool {
+    fn default() -> ThreadPool {
+        ThreadPool::new()
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+
+        for (_, unparker) in self.shared.sleepers.lock().unwrap().drain(..) {
+            unparker.unpark();
+        }
+
+        // If the pool is dropped by one of its own tasks, the worker running it can't be joined.
+        // It stops on its own once the task returns.
+        let current = thread::current().id();
+        for handle in self.threads.drain(..) {
+            if handle.thread().id() != current {
+                let _ = handle.join();
+            }
+        }
+
+        self.shared.drain();
+    }
+}
+
+impl fmt::Debug for ThreadPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadPool")
+            .field("num_threads", &self.num_threads())
+            .finish()
+    }
+}
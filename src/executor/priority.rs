@@ -0,0 +1,464 @@
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll};
+
+use std::sync::Mutex;
+
+use crate::parking::{Parker, Unparker};
+use crate::{JoinHandle, Spawn, Task};
+
+/// An executor that runs tasks in order of priority.
+///
+/// Every task is spawned with a priority, which is stored as its tag. Priority `0` is the highest,
+/// and there are as many priorities as the executor has levels. Each level has its own queue, and
+/// the executor picks the queue to run a task from according to its policy:
+///
+/// * By default, priorities are *strict*: a task is only run when all higher-priority queues are
+///   empty.
+/// * With [`weights()`], queues are served in *weighted-fair* order: every level gets a share of
+///   the runs proportional to its weight, so lower priorities make progress even under load.
+///
+/// In both cases, [`aging()`] can be used to bound how long a task waits. A task that has been
+/// passed over for the given number of runs is run next, regardless of its priority.
+///
+/// Tasks are run by the threads calling [`run_until()`], [`tick()`], or [`try_tick()`], which may
+/// be called from several threads at the same time. If running a task panics, the panic is
+/// propagated into the caller and the task is canceled. When the executor is dropped, its
+/// scheduled tasks are canceled, and so are tasks woken afterwards.
+///
+/// [`weights()`]: struct.PriorityBuilder.html#method.weights
+/// [`aging()`]: struct.PriorityBuilder.html#method.aging
+/// [`run_until()`]: #method.run_until
+/// [`tick()`]: #method.tick
+/// [`try_tick()`]: #method.try_tick
+///
+/// # Examples
+///
+/// ```
+/// use async_task::executor::PriorityExecutor;
+/// use std::sync::{Arc, Mutex};
+///
+/// let ex = PriorityExecutor::new(2);
+/// let log = Arc::new(Mutex::new(Vec::new()));
+///
+/// for &(name, priority) in &[("bulk", 1), ("control", 0)] {
+///     let log = log.clone();
+///     ex.spawn_with_priority(async move { log.lock().unwrap().push(name) }, priority);
+/// }
+/// while ex.try_tick() {}
+///
+/// // The control-plane task ran first even though it was spawned last.
+/// assert_eq!(*log.lock().unwrap(), ["control", "bulk"]);
+/// ```
+pub struct PriorityExecutor {
+    /// State shared with the schedule functions.
+    shared: Arc<Shared>,
+}
+
+/// Builds a [`PriorityExecutor`] with custom configuration.
+///
+/// [`PriorityExecutor`]: struct.PriorityExecutor.html
+#[derive(Debug)]
+pub struct PriorityBuilder {
+    /// Number of priority levels.
+    levels: usize,
+
+    /// Weights of the levels, if queues are served in weighted-fair order.
+    weights: Option<Vec<u32>>,
+
+    /// Number of runs a task may be passed over before it is run regardless of its priority.
+    aging: Option<u64>,
+}
+
+/// State shared with the schedule functions.
+struct Shared {
+    /// Number of priority levels.
+    levels: usize,
+
+    /// The priority queues.
+    queues: Mutex<Queues>,
+
+    /// Threads waiting for tasks to be scheduled.
+    sleepers: Mutex<Vec<Unparker>>,
+}
+
+/// The priority queues and the state of the policy.
+struct Queues {
+    /// Scheduled tasks of each level, along with the run count at which they were scheduled.
+    levels: Vec<VecDeque<(u64, Task<usize>)>>,
+
+    /// Weights of the levels, if queues are served in weighted-fair order.
+    weights: Option<Vec<u32>>,
+
+    /// Accumulated credit of each level in weighted-fair order.
+    credit: Vec<i64>,
+
+    /// Number of runs a task may be passed over before it is run regardless of its priority.
+    aging: Option<u64>,
+
+    /// Number of tasks taken out of the queues so far.
+    runs: u64,
+
+    /// Set when the executor is dropped, after which scheduled tasks are canceled.
+    closed: bool,
+}
+
+impl Queues {
+    /// Pushes a task into the queue of its priority.
+    fn push(&mut self, task: Task<usize>) {
+        let level = *task.tag();
+        self.levels[level].push_back((self.runs, task));
+    }
+
+    /// Takes the next task to run out of the queues.
+    fn pop(&mut self) -> Option<Task<usize>> {
+        let level = self.starving().or_else(|| match self.weights {
+            None => self.levels.iter().position(|q| !q.is_empty()),
+            Some(_) => self.weighted(),
+        })?;
+
+        self.runs += 1;
+        self.levels[level].pop_front().map(|(_, task)| task)
+    }
+
+    /// Returns the level of the task that has waited the longest, if it has waited for too long.
+    fn starving(&self) -> Option<usize> {
+        let aging = self.aging?;
+        let (level, since) = self
+            .levels
+            .iter()
+            .enumerate()
+            .filter_map(|(i, q)| q.front().map(|(since, _)| (i, *since)))
+            // On ties, prefer lower priorities because the policy would pick higher ones anyway.
+            .min_by_key(|&(i, since)| (since, Reverse(i)))?;
+
+        if self.runs - since >= aging {
+            Some(level)
+        } else {
+            None
+        }
+    }
+
+    /// Picks a level in smooth weighted round-robin order.
+    fn weighted(&mut self) -> Option<usize> {
+        let weights = self.weights.as_ref()?;
+        let mut total = 0;
+        let mut best: Option<usize> = None;
+
+        for (i, q) in self.levels.iter().enumerate() {
+            if q.is_empty() {
+                continue;
+            }
+
+            self.credit[i] += i64::from(weights[i]);
+            total += i64::from(weights[i]);
+
+            let better = match best {
+                None => true,
+                Some(b) => self.credit[i] > self.credit[b],
+            };
+            if better {
+                best = Some(i);
+            }
+        }
+
+        let best = best?;
+        self.credit[best] -= total;
+        Some(best)
+    }
+
+    /// Returns the total number of scheduled tasks.
+    fn len(&self) -> usize {
+        self.levels.iter().map(|q| q.len()).sum()
+    }
+}
+
+impl Shared {
+    /// Schedules a task and wakes up a thread waiting for tasks.
+    fn schedule(&self, task: Task<usize>) {
+        let mut queues = self.queues.lock().unwrap();
+
+        // Tasks scheduled after the executor is dropped are canceled because nothing is left to
+        // run them. Otherwise they would keep the queues alive through their schedule function.
+        if queues.closed {
+            drop(queues);
+            drop(task);
+            return;
+        }
+
+        queues.push(task);
+        drop(queues);
+
+        self.notify();
+    }
+
+    /// Wakes up a thread waiting for tasks, if there is one.
+    fn notify(&self) {
+        if let Some(unparker) = self.sleepers.lock().unwrap().pop() {
+            unparker.unpark();
+        }
+    }
+
+    /// Takes the next task to run out of the queues.
+    fn pop(&self) -> Option<Task<usize>> {
+        self.queues.lock().unwrap().pop()
+    }
+
+    /// Removes a thread that is no longer waiting from the list of sleepers.
+    ///
+    /// Returns `false` if the thread was already removed by [`notify()`], which means that the
+    /// wakeup meant for a newly scheduled task was spent on it.
+    ///
+    /// [`notify()`]: #method.notify
+    fn forget(&self, parker: &Parker) -> bool {
+        let mut sleepers = self.sleepers.lock().unwrap();
+        let len = sleepers.len();
+        sleepers.retain(|u| !u.will_unpark(parker));
+        sleepers.len() < len
+    }
+}
+
+impl PriorityBuilder {
+    /// Creates a builder for an executor with `levels` priority levels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is zero.
+    pub fn new(levels: usize) -> PriorityBuilder {
+        assert!(levels > 0, "a priority executor needs at least one level");
+        PriorityBuilder {
+            levels,
+            weights: None,
+            aging: None,
+        }
+    }
+
+    /// Serves the queues in weighted-fair order with the given weight for each level.
+    ///
+    /// A level with twice the weight of another gets about twice as many runs while both have
+    /// tasks scheduled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of weights is not equal to the number of levels, or if a weight is
+    /// zero.
+    pub fn weights(mut self, weights: impl Into<Vec<u32>>) -> PriorityBuilder {
+        let weights = weights.into();
+        assert_eq!(
+            weights.len(),
+            self.levels,
+            "there must be one weight per level"
+        );
+        assert!(weights.iter().all(|&w| w > 0), "weights must be non-zero");
+        self.weights = Some(weights);
+        self
+    }
+
+    /// Runs a task once it has been passed over for `max_wait` runs, regardless of its priority.
+    ///
+    /// This prevents starvation of low-priority tasks under strict priorities.
+    pub fn aging(mut self, max_wait: u64) -> PriorityBuilder {
+        self.aging = Some(max_wait);
+        self
+    }
+
+    /// Creates the executor.
+    pub fn build(self) -> PriorityExecutor {
+        PriorityExecutor {
+            shared: Arc::new(Shared {
+                levels: self.levels,
+                queues: Mutex::new(Queues {
+                    levels: (0..self.levels).map(|_| VecDeque::new()).collect(),
+                    weights: self.weights,
+                    credit: (0..self.levels).map(|_| 0).collect(),
+                    aging: self.aging,
+                    runs: 0,
+                    closed: false,
+                }),
+                sleepers: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+}
+
+impl PriorityExecutor {
+    /// Creates an executor with `levels` strict priority levels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is zero.
+    pub fn new(levels: usize) -> PriorityExecutor {
+        PriorityBuilder::new(levels).build()
+    }
+
+    /// Returns a builder for an executor with `levels` priority levels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is zero.
+    pub fn builder(levels: usize) -> PriorityBuilder {
+        PriorityBuilder::new(levels)
+    }
+
+    /// Returns the number of priority levels.
+    pub fn levels(&self) -> usize {
+        self.shared.levels
+    }
+
+    /// Spawns a future onto the executor with the given priority.
+    ///
+    /// Priority `0` is the highest. The priority is stored as the tag of the task.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `priority` is not less than the number of levels.
+    #[track_caller]
+    pub fn spawn_with_priority<F, R>(&self, future: F, priority: usize) -> JoinHandle<R, usize>
+    where
+        F: Future<Output = R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let levels = self.levels();
+        assert!(
+            priority < levels,
+            "priority {} is out of range for {} levels",
+            priority,
+            levels
+        );
+
+        let shared = self.shared.clone();
+        let schedule = move |task| shared.schedule(task);
+        let (task, handle) = crate::spawn(future, schedule, priority);
+        task.schedule();
+        handle
+    }
+
+    /// Runs a single scheduled task, if there is one.
+    ///
+    /// Returns `true` if a task was run.
+    pub fn try_tick(&self) -> bool {
+        match self.shared.pop() {
+            None => false,
+            Some(task) => {
+                task.run();
+                true
+            }
+        }
+    }
+
+    /// Runs a single scheduled task, blocking until one is available.
+    pub fn tick(&self) {
+        let parker = Parker::new();
+
+        while !self.try_tick() {
+            // Announce that this thread is going to sleep, then check once more for tasks
+            // scheduled in the meantime.
+            self.shared.sleepers.lock().unwrap().push(parker.unparker());
+            let ran = self.try_tick() || {
+                parker.park();
+                false
+            };
+
+            // If this thread was woken for a task but is about to return without looking for it,
+            // pass the wakeup on to another thread.
+            if !self.shared.forget(&parker) && ran {
+                self.shared.notify();
+            }
+
+            if ran {
+                break;
+            }
+        }
+    }
+
+    /// Runs the executor until `future` completes, and returns its output.
+    ///
+    /// Spawned tasks are run while waiting for the future. The thread is blocked when neither the
+    /// future nor any of the tasks can make progress.
+    pub fn run_until<F: Future>(&self, future: F) -> F::Output {
+        // Pin the future on the stack. It is shadowed so that it can't be moved afterwards.
+        let mut future = future;
+        let mut future = unsafe { Pin::new_unchecked(&mut future) };
+
+        let parker = Parker::new();
+
+        // Set when the future is woken, and initially so that it gets polled right away.
+        let woken = Arc::new(AtomicBool::new(true));
+        let waker = {
+            let woken = woken.clone();
+            let unparker = parker.unparker();
+            crate::waker_fn(move || {
+                woken.store(true, Ordering::SeqCst);
+                unparker.unpark();
+            })
+        };
+        let cx = &mut Context::from_waker(&waker);
+
+        loop {
+            if woken.swap(false, Ordering::SeqCst) {
+                if let Poll::Ready(output) = future.as_mut().poll(cx) {
+                    return output;
+                }
+            }
+
+            if self.try_tick() {
+                continue;
+            }
+
+            self.shared.sleepers.lock().unwrap().push(parker.unparker());
+            if !woken.load(Ordering::SeqCst) && !self.try_tick() {
+                parker.park();
+            }
+
+            // If this thread was woken for a task but might return before looking for it, pass
+            // the wakeup on to another thread.
+            if !self.shared.forget(&parker) && woken.load(Ordering::SeqCst) {
+                self.shared.notify();
+            }
+        }
+    }
+}
+
+/// Spawns futures with the lowest priority.
+impl Spawn for PriorityExecutor {
+    type Tag = usize;
+
+    #[track_caller]
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output, usize>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawn_with_priority(future, self.levels() - 1)
+    }
+}
+
+impl Drop for PriorityExecutor {
+    fn drop(&mut self) {
+        self.shared.queues.lock().unwrap().closed = true;
+
+        // Cancel the scheduled tasks.
+        loop {
+            match self.shared.pop() {
+                None => break,
+                Some(task) => drop(task),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for PriorityExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let queues = self.shared.queues.lock().unwrap();
+        f.debug_struct("PriorityExecutor")
+            .field("levels", &self.shared.levels)
+            .field("scheduled", &queues.len())
+            .finish()
+    }
+}
@@ -1,9 +1,13 @@
+use alloc::boxed::Box;
 use core::alloc::Layout;
 use core::cell::UnsafeCell;
 use core::fmt;
+use core::panic::Location;
+use core::ptr::NonNull;
 use core::sync::atomic::{AtomicUsize, Ordering};
 use core::task::Waker;
 
+use crate::name::TaskName;
 use crate::raw::TaskVTable;
 use crate::state::*;
 use crate::utils::{abort_on_panic, extend};
@@ -27,9 +31,52 @@ pub(crate) struct Header {
     /// In addition to the actual waker virtual table, it also contains pointers to several other
     /// methods necessary for bookkeeping the heap-allocated task.
     pub(crate) vtable: &'static TaskVTable,
+
+    /// Where the task was spawned, and its name.
+    pub(crate) origin: Origin,
+
+    /// The ID of the task.
+    #[cfg(any(
+        feature = "hooks",
+        feature = "registry",
+        feature = "tracing",
+        feature = "watchdog"
+    ))]
+    pub(crate) id: u64,
+
+    /// The span of the task, entered while its future is being polled.
+    #[cfg(feature = "tracing")]
+    pub(crate) span: tracing::Span,
+
+    /// The entry of the task in the registry of live tasks.
+    #[cfg(feature = "registry")]
+    pub(crate) entry: crate::registry::Entry,
+
+    /// Statistics of the task.
+    #[cfg(feature = "stats")]
+    pub(crate) stats: crate::stats::Stats,
 }
 
 impl Header {
+    /// Returns the location in the source code where the task was spawned.
+    #[inline]
+    pub(crate) fn location(&self) -> &'static Location<'static> {
+        self.origin.location()
+    }
+
+    /// Returns the name of the task.
+    #[inline]
+    pub(crate) fn name(&self) -> Option<&TaskName> {
+        self.origin.name()
+    }
+
+    /// Records that the task has just entered a new state.
+    #[inline]
+    pub(crate) fn touch(&self) {
+        #[cfg(feature = "registry")]
+        self.entry.touch();
+    }
+
     /// Cancels the task.
     ///
     /// This method will mark the task as closed, but it won't reschedule the task or drop its
@@ -50,7 +97,95 @@ impl Header {
                 Ordering::AcqRel,
                 Ordering::Acquire,
             ) {
-                Ok(_) => break,
+                Ok(_) => {
+                    self.record_cancel();
+                    break;
+                }
+                Err(s) => state = s,
+            }
+        }
+    }
+
+    /// Records that the task has just been closed before its future completed.
+    pub(crate) fn record_cancel(&self) {
+        self.touch();
+
+        #[cfg(feature = "hooks")]
+        crate::hooks::emit(|h| h.on_cancel(crate::hooks::TaskId::new(self)));
+        #[cfg(feature = "tracing")]
+        crate::trace::cancel(self);
+        #[cfg(feature = "metrics")]
+        crate::metrics::cancel();
+    }
+
+    /// Cancels the task and schedules it if needed.
+    ///
+    /// This method will mark the task as closed. If the task is neither scheduled nor running, it
+    /// will be scheduled one more time so that its future gets dropped by the executor.
+    pub(crate) fn cancel_and_schedule(&self) {
+        let ptr = self as *const Header as *const ();
+        let mut state = self.state.load(Ordering::Acquire);
+
+        loop {
+            // If the task has been completed or closed, it can't be canceled.
+            if state & (COMPLETED | CLOSED) != 0 {
+                break;
+            }
+
+            // If the task is not scheduled nor running, we'll need to schedule it.
+            let new = if state & (SCHEDULED | RUNNING) == 0 {
+                (state | SCHEDULED | CLOSED) + REFERENCE
+            } else {
+                state | CLOSED
+            };
+
+            // Mark the task as closed.
+            match self
+                .state
+                .compare_exchange_weak(state, new, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    self.record_cancel();
+
+                    // If the task is not scheduled nor running, schedule it one more time so
+                    // that its future gets dropped by the executor.
+                    if state & (SCHEDULED | RUNNING) == 0 {
+                        unsafe {
+                            (self.vtable.schedule)(ptr);
+                        }
+                    }
+
+                    // Notify the awaiter that the task has been closed.
+                    if state & AWAITER != 0 {
+                        self.notify(None);
+                    }
+
+                    break;
+                }
+                Err(s) => state = s,
+            }
+        }
+    }
+
+    /// Requests cancellation of the task.
+    ///
+    /// Returns `false` if the task has already been completed or closed.
+    pub(crate) fn request_cancel(&self) -> bool {
+        let mut state = self.state.load(Ordering::Acquire);
+
+        loop {
+            // A finished task is left alone so that it doesn't show up as being canceled.
+            if state & (COMPLETED | CLOSED) != 0 {
+                return false;
+            }
+
+            match self.state.compare_exchange_weak(
+                state,
+                state | CANCEL_REQUESTED,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return true,
                 Err(s) => state = s,
             }
         }
@@ -174,13 +309,83 @@ impl fmt::Debug for Header {
         let state = self.state.load(Ordering::SeqCst);
 
         f.debug_struct("Header")
+            .field("name", &self.name())
             .field("scheduled", &(state & SCHEDULED != 0))
             .field("running", &(state & RUNNING != 0))
             .field("completed", &(state & COMPLETED != 0))
             .field("closed", &(state & CLOSED != 0))
             .field("awaiter", &(state & AWAITER != 0))
             .field("handle", &(state & HANDLE != 0))
+            .field("cancel_requested", &(state & CANCEL_REQUESTED != 0))
             .field("ref_count", &(state / REFERENCE))
+            .field("location", &format_args!("{}", self.location()))
             .finish()
     }
 }
+
+/// Where a task was spawned, and its name, packed into a single pointer.
+///
+/// Most tasks are unnamed, so the pointer refers to their static spawn location. Only named tasks
+/// allocate, and point to a [`Named`] instead, which is marked by setting the lowest bit.
+pub(crate) struct Origin(NonNull<u8>);
+
+/// The out-of-line origin of a named task.
+struct Named {
+    /// Where the task was spawned.
+    location: &'static Location<'static>,
+
+    /// The name of the task.
+    name: TaskName,
+}
+
+// The origin only refers to a static location and an owned name, which are both `Send + Sync`.
+unsafe impl Send for Origin {}
+unsafe impl Sync for Origin {}
+
+impl Origin {
+    /// Creates the origin of a task spawned at `location`.
+    pub(crate) fn new(location: &'static Location<'static>, name: Option<TaskName>) -> Origin {
+        match name {
+            None => Origin(NonNull::from(location).cast()),
+            Some(name) => {
+                // Both `Location` and `Named` contain pointers, so the lowest bit is always free.
+                let named = Box::into_raw(Box::new(Named { location, name })) as *mut u8;
+                Origin(unsafe { NonNull::new_unchecked(named.wrapping_add(1)) })
+            }
+        }
+    }
+
+    /// Returns the out-of-line origin if the task is named.
+    #[inline]
+    fn named(&self) -> Option<&Named> {
+        let ptr = self.0.as_ptr();
+        if ptr as usize & 1 == 0 {
+            None
+        } else {
+            Some(unsafe { &*(ptr.wrapping_sub(1) as *const Named) })
+        }
+    }
+
+    /// Returns the location in the source code where the task was spawned.
+    #[inline]
+    pub(crate) fn location(&self) -> &'static Location<'static> {
+        match self.named() {
+            None => unsafe { &*(self.0.as_ptr() as *const Location<'static>) },
+            Some(named) => named.location,
+        }
+    }
+
+    /// Returns the name of the task.
+    #[inline]
+    pub(crate) fn name(&self) -> Option<&TaskName> {
+        self.named().map(|named| &named.name)
+    }
+}
+
+impl Drop for Origin {
+    fn drop(&mut self) {
+        if let Some(named) = self.named() {
+            unsafe { drop(Box::from_raw(named as *const Named as *mut Named)) }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::header::Header;
+use crate::utils::abort_on_panic;
+
+/// The hooks have not been set.
+const UNINITIALIZED: usize = 0;
+
+/// The hooks are being set.
+const INITIALIZING: usize = 1;
+
+/// The hooks have been set.
+const INITIALIZED: usize = 2;
+
+/// The state of the global hooks.
+static STATE: AtomicUsize = AtomicUsize::new(UNINITIALIZED);
+
+/// The global hooks, written once while initializing.
+static HOOKS: Slot = Slot(UnsafeCell::new(None));
+
+/// Storage for the global hooks.
+struct Slot(UnsafeCell<Option<&'static dyn TaskHooks>>);
+
+// The slot is only written once, before `STATE` becomes `INITIALIZED`, and only read after that.
+unsafe impl Sync for Slot {}
+
+/// Callbacks invoked at points in the lifecycle of every task.
+///
+/// Hooks are installed for the whole process with [`set_hooks()`], and are useful for collecting
+/// metrics, entering tracing spans, or recording task events into a flight recorder. All methods
+/// have empty default implementations, so only the interesting events need to be implemented.
+///
+/// Hooks are invoked synchronously from inside the task machinery, often by the thread that wakes
+/// or runs the task, so they should be quick. A panic inside a hook aborts the process.
+///
+/// **NOTE:** This trait is only available when the `hooks` feature for this crate is enabled.
+/// Without it, no hooks are invoked and there is no overhead.
+///
+/// [`set_hooks()`]: fn.set_hooks.html
+pub trait TaskHooks: Sync {
+    /// Called when a task is spawned.
+    fn on_spawn(&self, task: TaskId) {
+        let _ = task;
+    }
+
+    /// Called when a task is passed to its schedule function.
+    fn on_schedule(&self, task: TaskId) {
+        let _ = task;
+    }
+
+    /// Called right before the future of a task is polled.
+    fn on_poll_start(&self, task: TaskId) {
+        let _ = task;
+    }
+
+    /// Called right after the future of a task has been polled.
+    fn on_poll_end(&self, task: TaskId, outcome: PollOutcome) {
+        let _ = (task, outcome);
+    }
+
+    /// Called when a waker of a task is woken.
+    ///
+    /// If the waker was woken while another task was being polled on the same thread, `from` is
+    /// that task. It is always `None` when the `std` feature is disabled.
+    fn on_wake(&self, task: TaskId, from: Option<TaskId>) {
+        let _ = (task, from);
+    }
+
+    /// Called when a task is canceled.
+    fn on_cancel(&self, task: TaskId) {
+        let _ = task;
+    }
+
+    /// Called when the future of a task completes.
+    fn on_complete(&self, task: TaskId) {
+        let _ = task;
+    }
+
+    /// Called when a task is deallocated.
+    fn on_destroy(&self, task: TaskId) {
+        let _ = task;
+    }
+}
+
+/// The identity of a task passed to [`TaskHooks`].
+///
+/// IDs are assigned in order of allocation and are never reused, so they stay unique even after
+/// [`TaskHooks::on_destroy()`] has been called for a task.
+///
+/// **NOTE:** This type is only available when the `hooks` feature for this crate is enabled.
+///
+/// [`TaskHooks`]: trait.TaskHooks.html
+/// [`TaskHooks::on_destroy()`]: trait.TaskHooks.html#method.on_destroy
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskId(u64);
+
+impl TaskId {
+    /// Creates the ID of the task with the given header.
+    #[inline]
+    pub(crate) fn new(header: &Header) -> TaskId {
+        TaskId(header.id)
+    }
+
+    /// Returns the ID as a number.
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+/// The outcome of polling the future of a task, passed to [`TaskHooks::on_poll_end()`].
+///
+/// **NOTE:** This type is only available when the `hooks` feature for this crate is enabled.
+///
+/// [`TaskHooks::on_poll_end()`]: trait.TaskHooks.html#method.on_poll_end
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PollOutcome {
+    /// The future completed.
+    Ready,
+
+    /// The future is still pending.
+    Pending,
+
+    /// Polling the future panicked.
+    Panicked,
+}
+
+/// Installs the global task hooks.
+///
+/// The hooks can only be installed once, and are invoked for all tasks from then on.
+///
+/// **NOTE:** This function is only available when the `hooks` feature for this crate is enabled.
+///
+/// # Errors
+///
+/// Returns an error if hooks have already been installed.
+///
+/// # Examples
+///
+/// ```
+/// use async_task::{TaskHooks, TaskId};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// struct Counter(AtomicUsize);
+///
+/// impl TaskHooks for Counter {
+///     fn on_spawn(&self, _: TaskId) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// static SPAWNED: Counter = Counter(AtomicUsize::new(0));
+/// async_task::set_hooks(&SPAWNED).unwrap();
+///
+/// let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+/// assert_eq!(SPAWNED.0.load(Ordering::Relaxed), 1);
+/// ```
+pub fn set_hooks(hooks: &'static dyn TaskHooks) -> Result<(), SetHooksError> {
+    match STATE.compare_exchange(
+        UNINITIALIZED,
+        INITIALIZING,
+        Ordering::Acquire,
+        Ordering::Relaxed,
+    ) {
+        Ok(_) => {
+            unsafe {
+                *HOOKS.0.get() = Some(hooks);
+            }
+            STATE.store(INITIALIZED, Ordering::Release);
+            Ok(())
+        }
+        Err(_) => Err(SetHooksError(())),
+    }
+}
+
+/// The error returned by [`set_hooks()`] if hooks have already been installed.
+///
+/// **NOTE:** This type is only available when the `hooks` feature for this crate is enabled.
+///
+/// [`set_hooks()`]: fn.set_hooks.html
+#[derive(Debug)]
+pub struct SetHooksError(());
+
+impl fmt::Display for SetHooksError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("task hooks have already been installed")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SetHooksError {}
+
+/// Invokes `f` with the global hooks, if they have been installed.
+#[inline]
+pub(crate) fn emit(f: impl FnOnce(&dyn TaskHooks)) {
+    if STATE.load(Ordering::Acquire) == INITIALIZED {
+        // The slot is never written again after initialization.
+        if let Some(hooks) = unsafe { *HOOKS.0.get() } {
+            // Hooks are invoked from places that can't recover from panics.
+            abort_on_panic(|| f(hooks));
+        }
+    }
+}
+
+/// Returns the ID of the task being polled on the current thread, if any.
+#[inline]
+pub(crate) fn current() -> Option<TaskId> {
+    #[cfg(feature = "std")]
+    return crate::current::with(|header| header.map(TaskId::new));
+
+    #[cfg(not(feature = "std"))]
+    return None;
+}
@@ -1,13 +1,17 @@
 use core::fmt;
 use core::future::Future;
 use core::marker::{PhantomData, Unpin};
+use core::panic::Location;
 use core::pin::Pin;
 use core::ptr::NonNull;
 use core::sync::atomic::Ordering;
 use core::task::{Context, Poll, Waker};
+use core::time::Duration;
 
 use crate::header::Header;
+use crate::name::TaskName;
 use crate::state::*;
+use crate::timeout::{Timeout, Timer};
 
 /// A handle that awaits the result of a task.
 ///
@@ -39,44 +43,28 @@ impl<R, T> JoinHandle<R, T> {
         let header = ptr as *const Header;
 
         unsafe {
-            let mut state = (*header).state.load(Ordering::Acquire);
-
-            loop {
-                // If the task has been completed or closed, it can't be canceled.
-                if state & (COMPLETED | CLOSED) != 0 {
-                    break;
-                }
-
-                // If the task is not scheduled nor running, we'll need to schedule it.
-                let new = if state & (SCHEDULED | RUNNING) == 0 {
-                    (state | SCHEDULED | CLOSED) + REFERENCE
-                } else {
-                    state | CLOSED
-                };
-
-                // Mark the task as closed.
-                match (*header).state.compare_exchange_weak(
-                    state,
-                    new,
-                    Ordering::AcqRel,
-                    Ordering::Acquire,
-                ) {
-                    Ok(_) => {
-                        // If the task is not scheduled nor running, schedule it one more time so
-                        // that its future gets dropped by the executor.
-                        if state & (SCHEDULED | RUNNING) == 0 {
-                            ((*header).vtable.schedule)(ptr);
-                        }
+            (*header).cancel_and_schedule();
+        }
+    }
 
-                        // Notify the awaiter that the task has been closed.
-                        if state & AWAITER != 0 {
-                            (*header).notify(None);
-                        }
+    /// Requests the task to cancel itself.
+    ///
+    /// This is the first phase of a graceful cancellation. Unlike [`cancel()`], the future is not
+    /// dropped. Instead, the task is woken so that its future can observe the request through
+    /// [`cancel_requested()`] and finish on its own terms. If the future does not react, calling
+    /// [`cancel()`] afterwards cancels it the usual way.
+    ///
+    /// If the task has already completed, calling this method will have no effect.
+    ///
+    /// [`cancel()`]: #method.cancel
+    /// [`cancel_requested()`]: fn.cancel_requested.html
+    pub fn request_cancel(&self) {
+        let ptr = self.raw_task.as_ptr();
+        let header = ptr as *const Header;
 
-                        break;
-                    }
-                    Err(s) => state = s,
-                }
+        unsafe {
+            if (*header).request_cancel() {
+                self.waker().wake();
             }
         }
     }
@@ -92,6 +80,44 @@ impl<R, T> JoinHandle<R, T> {
         }
     }
 
+    /// Returns the name of the task, if it has one.
+    ///
+    /// Tasks are named with [`spawn_named()`] or [`Builder::name()`].
+    ///
+    /// [`spawn_named()`]: fn.spawn_named.html
+    /// [`Builder::name()`]: struct.Builder.html#method.name
+    pub fn name(&self) -> Option<&str> {
+        let ptr = self.raw_task.as_ptr();
+        let header = ptr as *const Header;
+
+        unsafe { (*header).name().map(TaskName::as_str) }
+    }
+
+    /// Returns the location in the source code where the task was spawned.
+    ///
+    /// This is the location of the call to [`spawn()`] or a similar function. Functions that
+    /// spawn tasks on behalf of their callers can be annotated with `#[track_caller]` to report
+    /// their own callers instead.
+    ///
+    /// [`spawn()`]: fn.spawn.html
+    pub fn spawn_location(&self) -> &'static Location<'static> {
+        let ptr = self.raw_task.as_ptr();
+        let header = ptr as *const Header;
+
+        unsafe { (*header).location() }
+    }
+
+    /// Returns a snapshot of the statistics of the task.
+    ///
+    /// **NOTE:** This method is only available when the `stats` feature for this crate is enabled.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::TaskStats {
+        let ptr = self.raw_task.as_ptr();
+        let header = ptr as *const Header;
+
+        unsafe { (*header).stats.snapshot() }
+    }
+
     /// Returns a waker associated with the task.
     pub fn waker(&self) -> Waker {
         let ptr = self.raw_task.as_ptr();
@@ -102,6 +128,30 @@ impl<R, T> JoinHandle<R, T> {
             Waker::from_raw(raw_waker)
         }
     }
+
+    /// Awaits the task for at most `duration`.
+    ///
+    /// The returned future resolves to `Err(Elapsed)` if the timer created by `timer` fires before
+    /// the task completes. See [`Timeout`] for more details.
+    ///
+    /// [`Timeout`]: struct.Timeout.html
+    pub fn timeout<M: Timer>(self, duration: Duration, timer: &M) -> Timeout<R, T, M::Sleep> {
+        Timeout::new(self, timer.sleep(duration))
+    }
+
+    /// Awaits the task until `deadline`.
+    ///
+    /// The returned future resolves to `Err(Elapsed)` if the timer created by `timer` fires before
+    /// the task completes. See [`Timeout`] for more details.
+    ///
+    /// [`Timeout`]: struct.Timeout.html
+    pub fn with_deadline<M: Timer>(
+        self,
+        deadline: M::Instant,
+        timer: &M,
+    ) -> Timeout<R, T, M::Sleep> {
+        Timeout::new(self, timer.sleep_until(deadline))
+    }
 }
 
 impl<R, T> Drop for JoinHandle<R, T> {
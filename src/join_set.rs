@@ -0,0 +1,241 @@
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use std::sync::Mutex;
+
+use crate::{waker_fn, JoinHandle};
+
+/// A collection of [`JoinHandle`]s that yields outputs in completion order.
+///
+/// Each member is polled with its own waker, which records the member in a shared ready list.
+/// That way, completing one task only polls its own handle rather than every handle in the set.
+///
+/// When the set is dropped, all remaining tasks are canceled.
+///
+/// **NOTE:** This type is only available when the `std` feature for this crate is enabled (it is
+/// by default).
+///
+/// # Examples
+///
+/// ```
+/// use async_task::JoinSet;
+/// use futures::executor::block_on;
+///
+/// let mut set = JoinSet::new();
+///
+/// for i in 0..3 {
+///     let (task, handle) = async_task::spawn(async move { i }, |_| {}, ());
+///     set.insert(handle);
+///     task.run();
+/// }
+///
+/// let mut outputs = Vec::new();
+/// while let Some(output) = block_on(set.join_next()) {
+///     outputs.push(output.unwrap());
+/// }
+/// outputs.sort();
+/// assert_eq!(outputs, [0, 1, 2]);
+/// ```
+///
+/// [`JoinHandle`]: struct.JoinHandle.html
+pub struct JoinSet<R, T> {
+    /// Slots holding the members of the set.
+    handles: Vec<Option<JoinHandle<R, T>>>,
+
+    /// Wakers pushing the slot with the same index into the ready list.
+    wakers: Vec<Waker>,
+
+    /// Indices of empty slots.
+    free: Vec<usize>,
+
+    /// Number of members in the set.
+    len: usize,
+
+    /// The ready list shared with the wakers.
+    ready: Arc<Ready>,
+}
+
+/// A list of slots whose handles need to be polled.
+struct Ready {
+    inner: Mutex<ReadyInner>,
+}
+
+struct ReadyInner {
+    /// Indices of slots that were woken.
+    queue: VecDeque<usize>,
+
+    /// The task blocked on `join_next()`.
+    waker: Option<Waker>,
+}
+
+impl Ready {
+    /// Marks a slot as ready and wakes the task blocked on the set.
+    fn push(&self, index: usize) {
+        let waker = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.queue.push_back(index);
+            inner.waker.take()
+        };
+
+        if let Some(w) = waker {
+            w.wake();
+        }
+    }
+
+    /// Takes the next ready slot, or registers `waker` if there is none.
+    fn pop(&self, waker: &Waker) -> Option<usize> {
+        let mut inner = self.inner.lock().unwrap();
+
+        match inner.queue.pop_front() {
+            Some(index) => Some(index),
+            None => {
+                match &inner.waker {
+                    Some(w) if w.will_wake(waker) => {}
+                    _ => inner.waker = Some(waker.clone()),
+                }
+                None
+            }
+        }
+    }
+}
+
+impl<R, T> JoinSet<R, T> {
+    /// Creates an empty set.
+    pub fn new() -> JoinSet<R, T> {
+        JoinSet {
+            handles: Vec::new(),
+            wakers: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+            ready: Arc::new(Ready {
+                inner: Mutex::new(ReadyInner {
+                    queue: VecDeque::new(),
+                    waker: None,
+                }),
+            }),
+        }
+    }
+
+    /// Returns the number of tasks in the set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no tasks in the set.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds a task to the set.
+    pub fn insert(&mut self, handle: JoinHandle<R, T>) {
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.handles[index] = Some(handle);
+                index
+            }
+            None => {
+                let index = self.handles.len();
+                let ready = self.ready.clone();
+                self.handles.push(Some(handle));
+                self.wakers.push(waker_fn(move || ready.push(index)));
+                index
+            }
+        };
+        self.len += 1;
+
+        // Poll the new member at least once so that it registers its waker.
+        self.ready.push(index);
+    }
+
+    /// Cancels all tasks in the set.
+    ///
+    /// The canceled tasks still need to be joined with [`join_next()`], which will yield `None`
+    /// for each of them once their futures are dropped.
+    ///
+    /// [`join_next()`]: #method.join_next
+    pub fn cancel_all(&self) {
+        for handle in self.handles.iter().flatten() {
+            handle.cancel();
+        }
+    }
+
+    /// Waits for the next task in the set to complete and returns its output.
+    ///
+    /// Returns `None` if the set is empty.
+    pub fn join_next(&mut self) -> JoinNext<'_, R, T> {
+        JoinNext { set: self }
+    }
+
+    /// Polls for the next task in the set to complete.
+    ///
+    /// Returns `Poll::Ready(None)` if the set is empty.
+    pub fn poll_join_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Option<R>>> {
+        while self.len > 0 {
+            let index = match self.ready.pop(cx.waker()) {
+                None => return Poll::Pending,
+                Some(index) => index,
+            };
+
+            // The slot might have been woken by a member that was already removed.
+            let handle = match &mut self.handles[index] {
+                None => continue,
+                Some(handle) => handle,
+            };
+
+            let cx = &mut Context::from_waker(&self.wakers[index]);
+            if let Poll::Ready(output) = Pin::new(handle).poll(cx) {
+                self.handles[index] = None;
+                self.free.push(index);
+                self.len -= 1;
+                return Poll::Ready(Some(output));
+            }
+        }
+
+        Poll::Ready(None)
+    }
+}
+
+impl<R, T> Default for JoinSet<R, T> {
+    fn default() -> JoinSet<R, T> {
+        JoinSet::new()
+    }
+}
+
+impl<R, T> Drop for JoinSet<R, T> {
+    fn drop(&mut self) {
+        self.cancel_all();
+    }
+}
+
+impl<R, T> fmt::Debug for JoinSet<R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinSet").field("len", &self.len).finish()
+    }
+}
+
+/// Future returned by [`JoinSet::join_next()`].
+///
+/// [`JoinSet::join_next()`]: struct.JoinSet.html#method.join_next
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct JoinNext<'a, R, T> {
+    set: &'a mut JoinSet<R, T>,
+}
+
+impl<R, T> Future for JoinNext<'_, R, T> {
+    type Output = Option<Option<R>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.set.poll_join_next(cx)
+    }
+}
+
+impl<R, T> fmt::Debug for JoinNext<'_, R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinNext").field("set", &self.set).finish()
+    }
+}
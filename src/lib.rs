@@ -85,13 +85,33 @@
 //! The `JoinHandle` future will then evaluate to `None`, but only after the task's future is
 //! dropped.
 //!
+//! Cancellation can also be graceful: [`JoinHandle::request_cancel()`] wakes the task without
+//! dropping its future, which can then observe the request through [`cancel_requested()`] and
+//! finish on its own.
+//!
+//! A [`JoinHandle`] can also be awaited with a deadline using [`JoinHandle::timeout()`]. Timers are
+//! provided by the executor through the [`Timer`] trait, and the task can optionally be canceled
+//! when the deadline passes.
+//!
 //! # Performance
 //!
 //! Task construction incurs a single allocation that holds its state, the schedule function, and
 //! the future or the result of the future if completed.
 //!
-//! The layout of a task is equivalent to 4 `usize`s followed by the schedule function, and then by
-//! a union of the future and its output.
+//! The layout of a task is equivalent to 5 `usize`s followed by the schedule function, and then by
+//! a union of the future and its output. One of them points to where the task was spawned, which
+//! is shown by [`Task::spawn_location()`] and [`JoinHandle::spawn_location()`]. Named tasks
+//! allocate their name separately, so unnamed tasks pay nothing for it.
+//!
+//! With the `registry` feature, every task header additionally links the task into a global list
+//! of live tasks, which can be inspected with [`dump()`] to debug tasks that went missing.
+//! Similarly, the `tracing` feature stores a [`tracing`] span in every task header. The span is
+//! entered while the task is polled, so that events emitted by its future are attributed to it,
+//! and wakes, cancellation, and completion are recorded as events inside it.
+//!
+//! The `metrics` feature adds no per-task state. Instead, it counts spawned, live, completed,
+//! canceled, and panicked tasks in process-wide atomic counters, which can be read with
+//! [`metrics()`].
 //!
 //! # Waking
 //!
@@ -106,15 +126,25 @@
 //! waker.wake_by_ref();
 //! ```
 //!
-//! This is useful for implementing single-future executors like [`block_on`].
+//! This is useful for implementing single-future executors like [`block_on`], which this crate
+//! also provides out of the box.
 //!
 //! [`spawn`]: fn.spawn.html
 //! [`spawn_local`]: fn.spawn_local.html
 //! [`waker_fn`]: fn.waker_fn.html
 //! [`Task`]: struct.Task.html
 //! [`JoinHandle`]: struct.JoinHandle.html
+//! [`JoinHandle::request_cancel()`]: struct.JoinHandle.html#method.request_cancel
+//! [`cancel_requested()`]: fn.cancel_requested.html
+//! [`JoinHandle::timeout()`]: struct.JoinHandle.html#method.timeout
+//! [`Timer`]: trait.Timer.html
+//! [`Task::spawn_location()`]: struct.Task.html#method.spawn_location
+//! [`JoinHandle::spawn_location()`]: struct.JoinHandle.html#method.spawn_location
 //! [`Waker`]: https://doc.rust-lang.org/std/task/struct.Waker.html
-//! [`block_on`]: https://github.com/async-rs/async-task/blob/master/examples/block.rs
+//! [`block_on`]: fn.block_on.html
+//! [`dump()`]: fn.dump.html
+//! [`metrics()`]: fn.metrics.html
+//! [`tracing`]: https://docs.rs/tracing
 
 #![no_std]
 #![warn(missing_docs, missing_debug_implementations, rust_2018_idioms)]
@@ -123,17 +153,94 @@
 
 extern crate alloc;
 
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "std")]
+mod block_on;
+mod builder;
+#[cfg(feature = "std")]
+mod cancellation;
+#[cfg(feature = "std")]
+mod current;
+#[cfg(any(feature = "registry", feature = "watchdog"))]
+mod debug_tag;
+#[cfg(feature = "executor")]
+pub mod executor;
 mod header;
+#[cfg(feature = "hooks")]
+mod hooks;
 mod join_handle;
+#[cfg(feature = "std")]
+mod join_set;
+#[cfg(feature = "std")]
+mod local_inbox;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "std")]
+mod misuse;
+mod name;
+#[cfg(feature = "std")]
+mod parking;
 mod raw;
+#[cfg(feature = "registry")]
+mod registry;
+#[cfg(feature = "std")]
+mod scope;
+#[cfg(feature = "sim")]
+pub mod sim;
+mod spawner;
 mod state;
+#[cfg(feature = "stats")]
+mod stats;
 mod task;
+#[cfg(feature = "std")]
+mod task_ref;
+mod timeout;
+#[cfg(feature = "timer")]
+pub mod timer;
+#[cfg(feature = "tracing")]
+mod trace;
 mod utils;
 mod waker_fn;
+#[cfg(feature = "watchdog")]
+mod watchdog;
 
+pub use crate::builder::Builder;
 pub use crate::join_handle::JoinHandle;
-pub use crate::task::{spawn, Task};
+pub use crate::name::TaskName;
+pub use crate::spawner::{LocalSpawn, Spawn, Spawner};
+pub use crate::task::{spawn, spawn_named, Task};
+pub use crate::timeout::{Elapsed, Timeout, Timer};
 pub use crate::waker_fn::waker_fn;
 
+#[cfg(feature = "std")]
+pub use crate::block_on::{block_on, block_on_timeout};
+#[cfg(feature = "std")]
+pub use crate::cancellation::{CancellationToken, Cancelled};
+#[cfg(feature = "std")]
+pub use crate::current::cancel_requested;
+#[cfg(any(feature = "registry", feature = "watchdog"))]
+pub use crate::debug_tag::debug_tags;
+#[cfg(feature = "hooks")]
+pub use crate::hooks::{set_hooks, PollOutcome, SetHooksError, TaskHooks, TaskId};
+#[cfg(feature = "std")]
+pub use crate::join_set::{JoinNext, JoinSet};
+#[cfg(feature = "std")]
+pub use crate::local_inbox::LocalInbox;
+#[cfg(feature = "metrics")]
+pub use crate::metrics::{metrics, Metrics};
+#[cfg(feature = "std")]
+pub use crate::misuse::{spawn_local_with_policy, LocalMisuse, Mailbox};
+#[cfg(feature = "registry")]
+pub use crate::registry::{dump, Checkpoint, ExitReport, TaskInfo};
+#[cfg(feature = "std")]
+pub use crate::scope::{spawn_scoped, ScopedJoinHandle};
+#[cfg(all(feature = "stats", feature = "tracing"))]
+pub use crate::stats::set_wake_storm_threshold;
+#[cfg(feature = "stats")]
+pub use crate::stats::{set_stats_clock, SetStatsClockError, TaskStats};
 #[cfg(feature = "std")]
 pub use crate::task::spawn_local;
+#[cfg(feature = "watchdog")]
+pub use crate::watchdog::{SlowPoll, Watchdog};
@@ -0,0 +1,229 @@
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::fmt;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::mem;
+
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+use crate::utils::thread_id;
+use crate::{JoinHandle, Task};
+
+/// An inbox that keeps the scheduling of local tasks on their origin thread.
+///
+/// Tasks created by [`spawn_local`] must only be run on the thread that spawned them, but their
+/// wakers can be woken on any thread, where they invoke the schedule function. A `LocalInbox`
+/// wraps a schedule function so that it is only ever invoked on the inbox's origin thread:
+///
+/// * Wakes on the origin thread invoke the schedule function directly.
+/// * Wakes on other threads push the task into a thread-safe inbox and notify the origin thread,
+///   which then hands the tasks to the schedule function by calling [`drain()`].
+///
+/// This way, the schedule function can push tasks into a thread-local queue, and local tasks can
+/// be safely woken by I/O threads or other executors.
+///
+/// When the inbox is dropped, the tasks in it are canceled. Tasks woken on other threads after
+/// that are leaked, because no thread is left that can drop them.
+///
+/// **NOTE:** This type is only available when the `std` feature for this crate is enabled (it is
+/// by default).
+///
+/// [`spawn_local`]: fn.spawn_local.html
+/// [`drain()`]: #method.drain
+///
+/// # Examples
+///
+/// ```
+/// use async_task::{LocalInbox, Task};
+/// use futures::channel::oneshot;
+/// use std::cell::{Cell, RefCell};
+/// use std::collections::VecDeque;
+/// use std::rc::Rc;
+/// use std::thread;
+///
+/// thread_local! {
+///     // The run queue of this thread's executor.
+///     static QUEUE: RefCell<VecDeque<Task<()>>> = RefCell::new(VecDeque::new());
+/// }
+///
+/// let inbox = LocalInbox::new(|task| QUEUE.with(|q| q.borrow_mut().push_back(task)));
+///
+/// let (s, r) = oneshot::channel();
+/// let out = Rc::new(Cell::new(0));
+/// let (task, _handle) = inbox.spawn(
+///     {
+///         let out = out.clone();
+///         async move { out.set(r.await.unwrap()) }
+///     },
+///     (),
+/// );
+/// task.schedule();
+///
+/// // The task is woken by another thread, but ends up in this thread's queue.
+/// thread::spawn(move || s.send(5).unwrap());
+///
+/// while out.get() == 0 {
+///     inbox.drain();
+///     while let Some(task) = QUEUE.with(|q| q.borrow_mut().pop_front()) {
+///         task.run();
+///     }
+///     if out.get() == 0 {
+///         thread::park();
+///     }
+/// }
+/// ```
+pub struct LocalInbox<T> {
+    /// State shared with the schedule functions.
+    inner: Arc<Inner<T>>,
+
+    /// Makes the type `!Send` and `!Sync`.
+    _marker: PhantomData<*mut ()>,
+}
+
+/// State shared with the schedule functions.
+struct Inner<T> {
+    /// The thread that owns the inbox.
+    origin: ThreadId,
+
+    /// The wrapped schedule function, only invoked on the origin thread.
+    schedule: Box<dyn Fn(Task<T>) + Send + Sync>,
+
+    /// Notifies the origin thread that tasks were pushed into the inbox.
+    notify: Box<dyn Fn() + Send + Sync>,
+
+    /// Tasks woken on other threads.
+    remote: Mutex<Remote<T>>,
+}
+
+/// Tasks woken on other threads.
+struct Remote<T> {
+    /// Tasks waiting to be handed to the schedule function.
+    tasks: VecDeque<Task<T>>,
+
+    /// Set when the inbox has been dropped.
+    closed: bool,
+}
+
+impl<T> Inner<T> {
+    /// Schedules a task on the origin thread, or pushes it into the inbox.
+    fn schedule(&self, task: Task<T>) {
+        if thread_id() == self.origin {
+            (self.schedule)(task);
+            return;
+        }
+
+        let mut remote = self.remote.lock().unwrap();
+        if remote.closed {
+            // The task must not be dropped on this thread.
+            mem::forget(task);
+            return;
+        }
+        remote.tasks.push_back(task);
+        drop(remote);
+
+        (self.notify)();
+    }
+}
+
+impl<T: Send + Sync + 'static> LocalInbox<T> {
+    /// Creates an inbox on the current thread that wraps `schedule`.
+    ///
+    /// The current thread is unparked whenever a task is woken on another thread.
+    pub fn new<S>(schedule: S) -> LocalInbox<T>
+    where
+        S: Fn(Task<T>) + Send + Sync + 'static,
+    {
+        let thread = thread::current();
+        LocalInbox::with_notify(schedule, move || thread.unpark())
+    }
+
+    /// Creates an inbox on the current thread that wraps `schedule`.
+    ///
+    /// The `notify` function is invoked whenever a task is woken on another thread.
+    pub fn with_notify<S, N>(schedule: S, notify: N) -> LocalInbox<T>
+    where
+        S: Fn(Task<T>) + Send + Sync + 'static,
+        N: Fn() + Send + Sync + 'static,
+    {
+        LocalInbox {
+            inner: Arc::new(Inner {
+                origin: thread_id(),
+                schedule: Box::new(schedule),
+                notify: Box::new(notify),
+                remote: Mutex::new(Remote {
+                    tasks: VecDeque::new(),
+                    closed: false,
+                }),
+            }),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a schedule function that routes tasks through this inbox.
+    pub fn schedule_fn(&self) -> impl Fn(Task<T>) + Send + Sync + 'static {
+        let inner = self.inner.clone();
+        move |task| inner.schedule(task)
+    }
+
+    /// Creates a local task whose wakes are routed through this inbox.
+    ///
+    /// This is like [`spawn_local`] with the schedule function returned by [`schedule_fn()`].
+    ///
+    /// [`spawn_local`]: fn.spawn_local.html
+    /// [`schedule_fn()`]: #method.schedule_fn
+    #[track_caller]
+    pub fn spawn<F, R>(&self, future: F, tag: T) -> (Task<T>, JoinHandle<R, T>)
+    where
+        F: Future<Output = R> + 'static,
+        R: 'static,
+    {
+        crate::spawn_local(future, self.schedule_fn(), tag)
+    }
+
+    /// Hands the tasks woken on other threads to the schedule function.
+    ///
+    /// Returns the number of tasks that were scheduled.
+    pub fn drain(&self) -> usize {
+        let tasks = mem::take(&mut self.inner.remote.lock().unwrap().tasks);
+        let count = tasks.len();
+
+        for task in tasks {
+            (self.inner.schedule)(task);
+        }
+        count
+    }
+
+    /// Returns the number of tasks woken on other threads that are waiting in the inbox.
+    pub fn len(&self) -> usize {
+        self.inner.remote.lock().unwrap().tasks.len()
+    }
+
+    /// Returns `true` if no tasks are waiting in the inbox.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for LocalInbox<T> {
+    fn drop(&mut self) {
+        // Cancel the tasks on this thread, which is where their futures must be dropped.
+        let tasks = {
+            let mut remote = self.inner.remote.lock().unwrap();
+            remote.closed = true;
+            mem::take(&mut remote.tasks)
+        };
+        drop(tasks);
+    }
+}
+
+impl<T> fmt::Debug for LocalInbox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalInbox")
+            .field("origin", &self.inner.origin)
+            .field("len", &self.inner.remote.lock().unwrap().tasks.len())
+            .finish()
+    }
+}
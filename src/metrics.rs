@@ -0,0 +1,168 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of tasks that have been allocated.
+static SPAWNED: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of tasks that have been deallocated.
+static DESTROYED: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of tasks whose future has completed.
+static COMPLETED: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of tasks that have been canceled.
+static CANCELED: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of tasks whose future has panicked.
+static PANICKED: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of wakes of tasks that were already completed or closed.
+static STALE_WAKES: AtomicUsize = AtomicUsize::new(0);
+
+/// Records that a task was allocated.
+#[inline]
+pub(crate) fn spawn() {
+    SPAWNED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records that a task was deallocated.
+#[inline]
+pub(crate) fn destroy() {
+    DESTROYED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records that the future of a task completed.
+#[inline]
+pub(crate) fn complete() {
+    COMPLETED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records that a task was canceled.
+#[inline]
+pub(crate) fn cancel() {
+    CANCELED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records that the future of a task panicked.
+#[inline]
+pub(crate) fn panic() {
+    PANICKED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records that a completed or closed task was woken.
+#[inline]
+pub(crate) fn stale_wake() {
+    STALE_WAKES.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Returns a snapshot of the process-wide task counters.
+///
+/// The counters are shared by all tasks, no matter which executor runs them, and cost a single
+/// relaxed atomic increment at the transitions they count. Sampling them periodically gives a
+/// cheap overview of executor health, for example a growing number of live tasks points to a leak.
+///
+/// **NOTE:** This function is only available when the `metrics` feature for this crate is enabled.
+///
+/// # Examples
+///
+/// ```
+/// let before = async_task::metrics();
+///
+/// let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+/// task.run();
+/// drop(handle);
+///
+/// let after = async_task::metrics();
+/// assert!(after.spawned() > before.spawned());
+/// ```
+pub fn metrics() -> Metrics {
+    // Load the deallocations first so that live tasks are never undercounted.
+    let destroyed = DESTROYED.load(Ordering::Relaxed);
+
+    Metrics {
+        spawned: SPAWNED.load(Ordering::Relaxed),
+        destroyed,
+        completed: COMPLETED.load(Ordering::Relaxed),
+        canceled: CANCELED.load(Ordering::Relaxed),
+        panicked: PANICKED.load(Ordering::Relaxed),
+        stale_wakes: STALE_WAKES.load(Ordering::Relaxed),
+    }
+}
+
+/// A snapshot of the process-wide task counters.
+///
+/// Returned by [`metrics()`]. The counters are loaded one by one while other threads may be
+/// updating them, so they are only approximately consistent with each other. The counters wrap
+/// around on overflow.
+///
+/// **NOTE:** This type is only available when the `metrics` feature for this crate is enabled.
+///
+/// [`metrics()`]: fn.metrics.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Number of tasks that have been allocated.
+    spawned: usize,
+
+    /// Number of tasks that have been deallocated.
+    destroyed: usize,
+
+    /// Number of tasks whose future has completed.
+    completed: usize,
+
+    /// Number of tasks that have been canceled.
+    canceled: usize,
+
+    /// Number of tasks whose future has panicked.
+    panicked: usize,
+
+    /// Number of wakes of tasks that were already completed or closed.
+    stale_wakes: usize,
+}
+
+impl Metrics {
+    /// Returns the number of tasks spawned so far.
+    pub fn spawned(&self) -> usize {
+        self.spawned
+    }
+
+    /// Returns the number of tasks that are currently allocated.
+    ///
+    /// A task stays allocated until its [`Task`], [`JoinHandle`] and all of its wakers are
+    /// dropped, even after it has completed or been canceled.
+    ///
+    /// [`Task`]: struct.Task.html
+    /// [`JoinHandle`]: struct.JoinHandle.html
+    pub fn alive(&self) -> usize {
+        self.spawned.wrapping_sub(self.destroyed)
+    }
+
+    /// Returns the number of tasks whose future has completed.
+    pub fn completed(&self) -> usize {
+        self.completed
+    }
+
+    /// Returns the number of tasks that were canceled before their future completed.
+    ///
+    /// This includes tasks canceled through their [`JoinHandle`], tasks whose [`Task`] reference
+    /// was dropped without being run, and tasks whose last waker was dropped after their
+    /// [`JoinHandle`].
+    ///
+    /// [`Task`]: struct.Task.html
+    /// [`JoinHandle`]: struct.JoinHandle.html
+    pub fn canceled(&self) -> usize {
+        self.canceled
+    }
+
+    /// Returns the number of tasks whose future panicked while being polled.
+    pub fn panicked(&self) -> usize {
+        self.panicked
+    }
+
+    /// Returns the number of wakes that had no effect because the task was already completed or
+    /// closed.
+    ///
+    /// Some of these are expected, but a high rate means that wakers outlive the tasks they
+    /// belong to, for example because they are never removed from the sources that wake them.
+    pub fn stale_wakes(&self) -> usize {
+        self.stale_wakes
+    }
+}
@@ -0,0 +1,427 @@
+use alloc::boxed::Box;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::any::Any;
+use core::cell::RefCell;
+use core::fmt;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::mem;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll};
+
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+use std::thread_local;
+
+use crate::name::TaskName;
+use crate::raw::RawTask;
+use crate::utils::thread_id;
+use crate::{Builder, JoinHandle, Task};
+
+thread_local! {
+    /// The mailbox registered on this thread.
+    static MAILBOX: RefCell<Weak<Inner>> = const { RefCell::new(Weak::new()) };
+}
+
+/// What to do when a local task is polled or dropped on the wrong thread.
+///
+/// This policy is passed to [`spawn_local_with_policy`].
+///
+/// [`spawn_local_with_policy`]: fn.spawn_local_with_policy.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LocalMisuse {
+    /// Panic, just like tasks created by [`spawn_local`] do.
+    ///
+    /// A panic while dropping the future aborts the process.
+    ///
+    /// [`spawn_local`]: fn.spawn_local.html
+    #[default]
+    Panic,
+
+    /// Leak the future instead of dropping it.
+    ///
+    /// Polling the task on the wrong thread still panics because it can't be sent back to its
+    /// origin thread, but the future is then leaked rather than dropped, so the process doesn't
+    /// abort. The task is canceled.
+    Leak,
+
+    /// Send the task back to its origin thread through the [`Mailbox`] registered there.
+    ///
+    /// A task run on the wrong thread is sent back without being polled, and run on the origin
+    /// thread instead. A future dropped on the wrong thread is dropped on the origin thread
+    /// instead. If the mailbox is gone, the task or future is leaked.
+    ///
+    /// [`Mailbox`]: struct.Mailbox.html
+    SendBack,
+}
+
+/// A per-thread mailbox receiving local tasks sent back by other threads.
+///
+/// Local tasks spawned with [`LocalMisuse::SendBack`] on a thread with a registered mailbox are
+/// sent back to it when polled or dropped on another thread. The origin thread is notified, and
+/// processes them by calling [`drain()`].
+///
+/// The mailbox is unregistered when dropped. Futures sent back after that are leaked.
+///
+/// **NOTE:** This type is only available when the `std` feature for this crate is enabled (it is
+/// by default).
+///
+/// [`LocalMisuse::SendBack`]: enum.LocalMisuse.html#variant.SendBack
+/// [`drain()`]: #method.drain
+pub struct Mailbox {
+    /// State shared with the tasks.
+    inner: Arc<Inner>,
+
+    /// Makes the type `!Send` and `!Sync`.
+    _marker: PhantomData<*mut ()>,
+}
+
+/// State shared with the tasks.
+struct Inner {
+    /// Notifies the origin thread that something was sent back.
+    notify: Box<dyn Fn() + Send + Sync>,
+
+    /// Tasks and futures sent back.
+    letters: Mutex<Letters>,
+}
+
+/// Tasks and futures sent back.
+struct Letters {
+    /// Tasks that were run on the wrong thread, each wrapped into a function that runs it.
+    tasks: Vec<Box<dyn FnOnce() + Send>>,
+
+    /// Futures that were dropped on the wrong thread.
+    futures: Vec<Orphan>,
+
+    /// Set when the mailbox has been dropped.
+    closed: bool,
+}
+
+/// A future that must be dropped on its origin thread.
+struct Orphan(#[allow(dead_code)] Pin<Box<dyn Any>>);
+
+// The future is only ever dropped on its origin thread, or leaked.
+unsafe impl Send for Orphan {}
+
+/// The way back to the origin thread of a task with the [`LocalMisuse::SendBack`] policy.
+///
+/// [`LocalMisuse::SendBack`]: enum.LocalMisuse.html#variant.SendBack
+struct Route {
+    /// The mailbox of the origin thread.
+    mailbox: Arc<Inner>,
+
+    /// Set when the task is run on the wrong thread, so that it gets sent back when scheduled.
+    misplaced: AtomicBool,
+}
+
+impl Inner {
+    /// Sends back a task to be run.
+    fn send_task(&self, run: Box<dyn FnOnce() + Send>) {
+        let mut letters = self.letters.lock().unwrap();
+        if letters.closed {
+            // The task must not be dropped on this thread.
+            mem::forget(run);
+            return;
+        }
+        letters.tasks.push(run);
+        drop(letters);
+
+        (self.notify)();
+    }
+
+    /// Sends back a future to be dropped.
+    fn send_future(&self, future: Orphan) {
+        let mut letters = self.letters.lock().unwrap();
+        if letters.closed {
+            // The future must not be dropped on this thread.
+            mem::forget(future);
+            return;
+        }
+        letters.futures.push(future);
+        drop(letters);
+
+        (self.notify)();
+    }
+}
+
+impl Mailbox {
+    /// Registers a mailbox for the current thread.
+    ///
+    /// The current thread is unparked whenever something is sent back. A previously registered
+    /// mailbox keeps receiving tasks spawned while it was registered.
+    pub fn register() -> Mailbox {
+        let thread = thread::current();
+        Mailbox::register_with_notify(move || thread.unpark())
+    }
+
+    /// Registers a mailbox for the current thread.
+    ///
+    /// The `notify` function is invoked whenever something is sent back.
+    pub fn register_with_notify<N>(notify: N) -> Mailbox
+    where
+        N: Fn() + Send + Sync + 'static,
+    {
+        let inner = Arc::new(Inner {
+            notify: Box::new(notify),
+            letters: Mutex::new(Letters {
+                tasks: Vec::new(),
+                futures: Vec::new(),
+                closed: false,
+            }),
+        });
+        MAILBOX.with(|m| *m.borrow_mut() = Arc::downgrade(&inner));
+
+        Mailbox {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Processes the tasks sent back to this thread.
+    ///
+    /// Tasks run on the wrong thread are run on this thread, and futures dropped on the wrong
+    /// thread are dropped. Returns the number of tasks that were processed.
+    pub fn drain(&self) -> usize {
+        let (tasks, futures) = {
+            let mut letters = self.inner.letters.lock().unwrap();
+            (
+                mem::take(&mut letters.tasks),
+                mem::take(&mut letters.futures),
+            )
+        };
+        let count = tasks.len() + futures.len();
+
+        for run in tasks {
+            run();
+        }
+        drop(futures);
+        count
+    }
+}
+
+impl Drop for Mailbox {
+    fn drop(&mut self) {
+        MAILBOX.with(|m| {
+            let mut m = m.borrow_mut();
+            if m.as_ptr() == Arc::as_ptr(&self.inner) {
+                *m = Weak::new();
+            }
+        });
+
+        // Cancel the tasks and drop the futures on this thread, which is where they must be dropped.
+        let (tasks, futures) = {
+            let mut letters = self.inner.letters.lock().unwrap();
+            letters.closed = true;
+            (
+                mem::take(&mut letters.tasks),
+                mem::take(&mut letters.futures),
+            )
+        };
+        drop(tasks);
+        drop(futures);
+    }
+}
+
+impl fmt::Debug for Mailbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let letters = self.inner.letters.lock().unwrap();
+        f.debug_struct("Mailbox")
+            .field("tasks", &letters.tasks.len())
+            .field("futures", &letters.futures.len())
+            .finish()
+    }
+}
+
+/// Creates a new local task with a policy for misuse on the wrong thread.
+///
+/// This is like [`spawn_local`], except that polling or dropping the task on a thread other than
+/// the one that spawned it is handled according to `policy` instead of always panicking. An
+/// executor shutting down on another thread can then drop local tasks without crashing the
+/// process.
+///
+/// The future is always allocated separately from the task so that it can be sent back to its
+/// origin thread.
+///
+/// **NOTE:** This function is only available when the `std` feature for this crate is enabled (it
+/// is by default).
+///
+/// # Panics
+///
+/// Panics if `policy` is [`LocalMisuse::SendBack`] and no [`Mailbox`] is registered on the
+/// current thread.
+///
+/// [`spawn_local`]: fn.spawn_local.html
+/// [`LocalMisuse::SendBack`]: enum.LocalMisuse.html#variant.SendBack
+/// [`Mailbox`]: struct.Mailbox.html
+///
+/// # Examples
+///
+/// ```
+/// use async_task::LocalMisuse;
+/// use std::rc::Rc;
+/// use std::thread;
+///
+/// let val = Rc::new(1);
+/// let future = async move { *val };
+///
+/// let (task, handle) =
+///     async_task::spawn_local_with_policy(future, |_| {}, (), LocalMisuse::Leak);
+///
+/// // Dropping the task on another thread leaks the future rather than panicking.
+/// thread::spawn(move || drop(task)).join().unwrap();
+/// ```
+#[track_caller]
+pub fn spawn_local_with_policy<F, R, S, T>(
+    future: F,
+    schedule: S,
+    tag: T,
+    policy: LocalMisuse,
+) -> (Task<T>, JoinHandle<R, T>)
+where
+    F: Future<Output = R> + 'static,
+    R: 'static,
+    S: Fn(Task<T>) + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+{
+    Builder::new().spawn_local_with_policy(future, schedule, tag, policy)
+}
+
+/// Creates a new local task with a policy for misuse on the wrong thread, and the given name.
+#[track_caller]
+pub(crate) fn spawn<F, R, S, T>(
+    future: F,
+    schedule: S,
+    tag: T,
+    policy: LocalMisuse,
+    name: Option<TaskName>,
+) -> (Task<T>, JoinHandle<R, T>)
+where
+    F: Future<Output = R> + 'static,
+    R: 'static,
+    S: Fn(Task<T>) + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+{
+    let route = match policy {
+        LocalMisuse::SendBack => {
+            let mailbox = MAILBOX.with(|m| m.borrow().upgrade());
+            let mailbox = mailbox.expect("no mailbox registered on this thread");
+            Some(Arc::new(Route {
+                mailbox,
+                misplaced: AtomicBool::new(false),
+            }))
+        }
+        _ => None,
+    };
+
+    // A task run on the wrong thread wakes itself, so that it gets scheduled again right after
+    // the run, and is then sent back instead of being handed to `schedule`.
+    let schedule = {
+        let route = route.clone();
+        move |task: Task<T>| match &route {
+            Some(route) if route.misplaced.swap(false, Ordering::SeqCst) => {
+                route.mailbox.send_task(Box::new(move || {
+                    task.run();
+                }))
+            }
+            _ => schedule(task),
+        }
+    };
+
+    let future = Guarded {
+        origin: thread_id(),
+        policy,
+        route,
+        name: name.clone(),
+        future: Some(Box::pin(future)),
+    };
+
+    let raw_task = RawTask::<_, R, _, T>::allocate(future, schedule, tag, name);
+    let task = Task {
+        raw_task,
+        _marker: PhantomData,
+    };
+    let handle = JoinHandle {
+        raw_task,
+        _marker: PhantomData,
+    };
+    (task, handle)
+}
+
+/// A future that handles being polled or dropped on the wrong thread.
+struct Guarded<F: 'static> {
+    /// The thread that spawned the task.
+    origin: ThreadId,
+
+    /// What to do on the wrong thread.
+    policy: LocalMisuse,
+
+    /// The way back to the origin thread, if the policy is to send the task back.
+    route: Option<Arc<Route>>,
+
+    /// The name of the task, for panic messages.
+    name: Option<TaskName>,
+
+    /// The future, until it is dropped.
+    future: Option<Pin<Box<F>>>,
+}
+
+impl<F: 'static> Drop for Guarded<F> {
+    fn drop(&mut self) {
+        let future = match self.future.take() {
+            None => return,
+            Some(future) => future,
+        };
+
+        if self.origin == thread_id() {
+            drop(future);
+            return;
+        }
+
+        match (self.policy, &self.route) {
+            (LocalMisuse::SendBack, Some(route)) => route.mailbox.send_future(Orphan(future)),
+            (LocalMisuse::Panic, _) => {
+                mem::forget(future);
+                misuse(&self.name, "dropped");
+            }
+            _ => mem::forget(future),
+        }
+    }
+}
+
+impl<F: Future> Future for Guarded<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        // The future is boxed, so `Guarded` itself is never pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        if this.origin != thread_id() {
+            match (this.policy, &this.route) {
+                (LocalMisuse::SendBack, Some(route)) => {
+                    route.misplaced.store(true, Ordering::SeqCst);
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                _ => misuse(&this.name, "polled"),
+            }
+        }
+
+        match &mut this.future {
+            None => Poll::Pending,
+            Some(future) => future.as_mut().poll(cx),
+        }
+    }
+}
+
+/// Panics because a local task was used by a thread that didn't spawn it.
+fn misuse(name: &Option<TaskName>, action: &str) -> ! {
+    match name {
+        None => panic!("local task {} by a thread that didn't spawn it", action),
+        Some(name) => panic!(
+            "local task {:?} {} by a thread that didn't spawn it",
+            name, action
+        ),
+    }
+}
@@ -0,0 +1,134 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+/// The name of a task.
+///
+/// Names are given to tasks with [`spawn_named()`] or [`Builder::name()`], and are meant for
+/// humans: they show up in the [`Debug`] output of [`Task`] and [`JoinHandle`], in panic messages
+/// raised by this crate, in task dumps, and in tracing spans. Unlike tags, names have the same
+/// type in every executor, so shared tooling can read them.
+///
+/// A name is either a `&'static str`, which costs nothing to create, or an `Arc<str>` for names
+/// built at runtime. Both convert into a `TaskName` with [`From`].
+///
+/// [`spawn_named()`]: fn.spawn_named.html
+/// [`Builder::name()`]: struct.Builder.html#method.name
+/// [`Debug`]: https://doc.rust-lang.org/std/fmt/trait.Debug.html
+/// [`Task`]: struct.Task.html
+/// [`JoinHandle`]: struct.JoinHandle.html
+/// [`From`]: https://doc.rust-lang.org/std/convert/trait.From.html
+///
+/// # Examples
+///
+/// ```
+/// use async_task::TaskName;
+/// use std::sync::Arc;
+///
+/// let name = TaskName::from("listener");
+/// assert_eq!(name, "listener");
+///
+/// let name = TaskName::from(Arc::<str>::from(format!("conn-{}", 7)));
+/// assert_eq!(name.as_str(), "conn-7");
+/// ```
+#[derive(Clone)]
+pub struct TaskName(Repr);
+
+/// The representation of a task name.
+#[derive(Clone)]
+enum Repr {
+    /// A name known at compile time.
+    Static(&'static str),
+
+    /// A name built at runtime.
+    Shared(Arc<str>),
+}
+
+impl TaskName {
+    /// Returns the name as a string slice.
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            Repr::Static(name) => name,
+            Repr::Shared(name) => name,
+        }
+    }
+}
+
+impl Deref for TaskName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for TaskName {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for TaskName {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&'static str> for TaskName {
+    fn from(name: &'static str) -> TaskName {
+        TaskName(Repr::Static(name))
+    }
+}
+
+impl From<Arc<str>> for TaskName {
+    fn from(name: Arc<str>) -> TaskName {
+        TaskName(Repr::Shared(name))
+    }
+}
+
+impl From<String> for TaskName {
+    fn from(name: String) -> TaskName {
+        TaskName(Repr::Shared(name.into()))
+    }
+}
+
+impl PartialEq for TaskName {
+    fn eq(&self, other: &TaskName) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for TaskName {}
+
+impl PartialEq<str> for TaskName {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for TaskName {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Hash for TaskName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl fmt::Debug for TaskName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for TaskName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
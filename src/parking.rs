@@ -0,0 +1,91 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Waker;
+
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+/// Blocks the current thread until it is notified.
+///
+/// Unlike bare `thread::park()`, a parker ignores spurious wakeups and unparks sent by code other
+/// than its own [`Unparker`]s.
+pub(crate) struct Parker {
+    inner: Arc<Inner>,
+}
+
+/// Notifies a [`Parker`].
+#[derive(Clone)]
+pub(crate) struct Unparker {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    /// Set when the parker has been notified.
+    notified: AtomicBool,
+
+    /// The thread owning the parker.
+    thread: Thread,
+}
+
+impl Parker {
+    /// Creates a parker for the current thread.
+    pub(crate) fn new() -> Parker {
+        Parker {
+            inner: Arc::new(Inner {
+                notified: AtomicBool::new(false),
+                thread: thread::current(),
+            }),
+        }
+    }
+
+    /// Blocks until notified.
+    pub(crate) fn park(&self) {
+        while !self.inner.notified.swap(false, Ordering::SeqCst) {
+            thread::park();
+        }
+    }
+
+    /// Blocks until notified or until `deadline` is reached.
+    ///
+    /// Returns `true` if notified.
+    pub(crate) fn park_deadline(&self, deadline: Instant) -> bool {
+        loop {
+            if self.inner.notified.swap(false, Ordering::SeqCst) {
+                return true;
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            thread::park_timeout(deadline - now);
+        }
+    }
+
+    /// Returns a handle that notifies this parker.
+    pub(crate) fn unparker(&self) -> Unparker {
+        Unparker {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl Unparker {
+    /// Notifies the parker.
+    pub(crate) fn unpark(&self) {
+        if !self.inner.notified.swap(true, Ordering::SeqCst) {
+            self.inner.thread.unpark();
+        }
+    }
+
+    /// Returns `true` if this unparker notifies `parker`.
+    #[cfg(feature = "executor")]
+    pub(crate) fn will_unpark(&self, parker: &Parker) -> bool {
+        Arc::ptr_eq(&self.inner, &parker.inner)
+    }
+
+    /// Converts the unparker into a waker.
+    pub(crate) fn into_waker(self) -> Waker {
+        crate::waker_fn(move || self.unpark())
+    }
+}
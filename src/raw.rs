@@ -3,12 +3,16 @@ use core::cell::UnsafeCell;
 use core::future::Future;
 use core::marker::PhantomData;
 use core::mem::{self, ManuallyDrop};
+use core::panic::Location;
 use core::pin::Pin;
 use core::ptr::NonNull;
 use core::sync::atomic::{AtomicUsize, Ordering};
 use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
 
-use crate::header::Header;
+use crate::header::{Header, Origin};
+#[cfg(feature = "hooks")]
+use crate::hooks::{PollOutcome, TaskId};
+use crate::name::TaskName;
 use crate::state::*;
 use crate::utils::{abort, abort_on_panic, extend};
 use crate::Task;
@@ -35,6 +39,10 @@ pub(crate) struct TaskVTable {
 
     /// Creates a new waker associated with the task.
     pub(crate) clone_waker: unsafe fn(ptr: *const ()) -> RawWaker,
+
+    /// Formats the tag of the task.
+    #[cfg(any(feature = "registry", feature = "watchdog"))]
+    pub(crate) fmt_tag: unsafe fn(*const (), &mut core::fmt::Formatter<'_>) -> core::fmt::Result,
 }
 
 /// Memory layout of a task.
@@ -91,6 +99,7 @@ impl<F, R, S, T> RawTask<F, R, S, T>
 where
     F: Future<Output = R> + 'static,
     S: Fn(Task<T>) + Send + Sync + 'static,
+    T: 'static,
 {
     const RAW_WAKER_VTABLE: RawWakerVTable = RawWakerVTable::new(
         Self::clone_waker,
@@ -101,11 +110,24 @@ where
 
     /// Allocates a task with the given `future` and `schedule` function.
     ///
-    /// It is assumed that initially only the `Task` reference and the `JoinHandle` exist.
-    pub(crate) fn allocate(future: F, schedule: S, tag: T) -> NonNull<()> {
+    /// It is assumed that initially only the `Task` reference and the `JoinHandle` exist. The
+    /// caller is recorded as the location where the task was spawned.
+    #[track_caller]
+    pub(crate) fn allocate(future: F, schedule: S, tag: T, name: Option<TaskName>) -> NonNull<()> {
         // Compute the layout of the task for allocation. Abort if the computation fails.
         let task_layout = abort_on_panic(|| Self::task_layout());
 
+        #[cfg(any(
+            feature = "hooks",
+            feature = "registry",
+            feature = "tracing",
+            feature = "watchdog"
+        ))]
+        let id = crate::utils::next_task_id();
+
+        #[cfg(feature = "tracing")]
+        let span = crate::trace::span(id, name.as_deref());
+
         unsafe {
             // Allocate enough space for the entire task.
             let raw_task = match NonNull::new(alloc::alloc::alloc(task_layout.layout) as *mut ()) {
@@ -127,11 +149,27 @@ where
                     destroy: Self::destroy,
                     run: Self::run,
                     clone_waker: Self::clone_waker,
+                    #[cfg(any(feature = "registry", feature = "watchdog"))]
+                    fmt_tag: Self::fmt_tag,
                 },
+                origin: Origin::new(Location::caller(), name),
+                #[cfg(any(
+                    feature = "hooks",
+                    feature = "registry",
+                    feature = "tracing",
+                    feature = "watchdog"
+                ))]
+                id,
+                #[cfg(feature = "tracing")]
+                span,
+                #[cfg(feature = "registry")]
+                entry: crate::registry::Entry::new(),
+                #[cfg(feature = "stats")]
+                stats: crate::stats::Stats::new(),
             });
 
             // Write the tag as the second field of the task.
-            (raw.tag as *mut T).write(tag);
+            raw.tag.write(tag);
 
             // Write the schedule function as the third field of the task.
             (raw.schedule as *mut S).write(schedule);
@@ -139,6 +177,15 @@ where
             // Write the future as the fourth field of the task.
             raw.future.write(future);
 
+            // Make the task visible in the registry of live tasks.
+            #[cfg(feature = "registry")]
+            crate::registry::link(raw.header);
+
+            #[cfg(feature = "hooks")]
+            crate::hooks::emit(|h| h.on_spawn(TaskId::new(&*raw.header)));
+            #[cfg(feature = "metrics")]
+            crate::metrics::spawn();
+
             raw_task
         }
     }
@@ -204,11 +251,23 @@ where
 
         let raw = Self::from_ptr(ptr);
 
+        #[cfg(feature = "hooks")]
+        crate::hooks::emit(|h| h.on_wake(TaskId::new(&*raw.header), crate::hooks::current()));
+        #[cfg(feature = "tracing")]
+        crate::trace::wake(&*raw.header);
+        #[cfg(feature = "stats")]
+        (*raw.header).stats.wake();
+
         let mut state = (*raw.header).state.load(Ordering::Acquire);
 
         loop {
             // If the task is completed or closed, it can't be woken up.
             if state & (COMPLETED | CLOSED) != 0 {
+                #[cfg(feature = "metrics")]
+                crate::metrics::stale_wake();
+                #[cfg(feature = "stats")]
+                (*raw.header).stats.stale_wake();
+
                 // Drop the waker.
                 Self::drop_waker(ptr);
                 break;
@@ -225,6 +284,9 @@ where
                     Ordering::Acquire,
                 ) {
                     Ok(_) => {
+                        #[cfg(feature = "stats")]
+                        (*raw.header).stats.redundant_wake(&*raw.header);
+
                         // Drop the waker.
                         Self::drop_waker(ptr);
                         break;
@@ -246,6 +308,9 @@ where
                             // Schedule the task.
                             Self::schedule(ptr);
                         } else {
+                            #[cfg(feature = "stats")]
+                            (*raw.header).stats.running_wake();
+
                             // Drop the waker.
                             Self::drop_waker(ptr);
                         }
@@ -262,11 +327,22 @@ where
     unsafe fn wake_by_ref(ptr: *const ()) {
         let raw = Self::from_ptr(ptr);
 
+        #[cfg(feature = "hooks")]
+        crate::hooks::emit(|h| h.on_wake(TaskId::new(&*raw.header), crate::hooks::current()));
+        #[cfg(feature = "tracing")]
+        crate::trace::wake(&*raw.header);
+        #[cfg(feature = "stats")]
+        (*raw.header).stats.wake();
+
         let mut state = (*raw.header).state.load(Ordering::Acquire);
 
         loop {
             // If the task is completed or closed, it can't be woken up.
             if state & (COMPLETED | CLOSED) != 0 {
+                #[cfg(feature = "metrics")]
+                crate::metrics::stale_wake();
+                #[cfg(feature = "stats")]
+                (*raw.header).stats.stale_wake();
                 break;
             }
 
@@ -280,7 +356,11 @@ where
                     Ordering::AcqRel,
                     Ordering::Acquire,
                 ) {
-                    Ok(_) => break,
+                    Ok(_) => {
+                        #[cfg(feature = "stats")]
+                        (*raw.header).stats.redundant_wake(&*raw.header);
+                        break;
+                    }
                     Err(s) => state = s,
                 }
             } else {
@@ -302,18 +382,28 @@ where
                         // If the task is not running, now is the time to schedule.
                         if state & RUNNING == 0 {
                             // If the reference count overflowed, abort.
-                            if state > isize::max_value() as usize {
+                            if state > isize::MAX as usize {
                                 abort();
                             }
 
                             // Schedule the task. There is no need to call `Self::schedule(ptr)`
                             // because the schedule function cannot be destroyed while the waker is
                             // still alive.
+                            (*raw.header).touch();
+
+                            #[cfg(feature = "hooks")]
+                            crate::hooks::emit(|h| h.on_schedule(TaskId::new(&*raw.header)));
+                            #[cfg(feature = "stats")]
+                            (*raw.header).stats.schedule();
+
                             let task = Task {
                                 raw_task: NonNull::new_unchecked(ptr as *mut ()),
                                 _marker: PhantomData,
                             };
                             (*raw.schedule)(task);
+                        } else {
+                            #[cfg(feature = "stats")]
+                            (*raw.header).stats.running_wake();
                         }
 
                         break;
@@ -333,7 +423,7 @@ where
         let state = (*raw.header).state.fetch_add(REFERENCE, Ordering::Relaxed);
 
         // If the reference count overflowed, abort.
-        if state > isize::max_value() as usize {
+        if state > isize::MAX as usize {
             abort();
         }
 
@@ -361,6 +451,7 @@ where
                 (*raw.header)
                     .state
                     .store(SCHEDULED | CLOSED | REFERENCE, Ordering::Release);
+                (*raw.header).record_cancel();
                 Self::schedule(ptr);
             } else {
                 // Otherwise, destroy the task right away.
@@ -393,6 +484,12 @@ where
     /// its schedule function.
     unsafe fn schedule(ptr: *const ()) {
         let raw = Self::from_ptr(ptr);
+        (*raw.header).touch();
+
+        #[cfg(feature = "hooks")]
+        crate::hooks::emit(|h| h.on_schedule(TaskId::new(&*raw.header)));
+        #[cfg(feature = "stats")]
+        (*raw.header).stats.schedule();
 
         // If the schedule function has captured variables, create a temporary waker that prevents
         // the task from getting deallocated while the function is being invoked.
@@ -434,19 +531,40 @@ where
         let raw = Self::from_ptr(ptr);
         let task_layout = Self::task_layout();
 
+        #[cfg(feature = "hooks")]
+        crate::hooks::emit(|h| h.on_destroy(TaskId::new(&*raw.header)));
+        #[cfg(feature = "metrics")]
+        crate::metrics::destroy();
+
+        // Remove the task from the registry before its tag gets dropped.
+        #[cfg(feature = "registry")]
+        crate::registry::unlink(raw.header);
+
         // We need a safeguard against panics because destructors can panic.
         abort_on_panic(|| {
             // Drop the schedule function.
             (raw.schedule as *mut S).drop_in_place();
 
-            // Drop the tag.
-            (raw.tag as *mut T).drop_in_place();
+            // Drop the tag and the name.
+            raw.tag.drop_in_place();
+            core::ptr::addr_of_mut!((*(raw.header as *mut Header)).origin).drop_in_place();
+
+            // Close the span.
+            #[cfg(feature = "tracing")]
+            core::ptr::addr_of_mut!((*(raw.header as *mut Header)).span).drop_in_place();
         });
 
         // Finally, deallocate the memory reserved by the task.
         alloc::alloc::dealloc(ptr as *mut u8, task_layout.layout);
     }
 
+    /// Formats the tag of a task.
+    #[cfg(any(feature = "registry", feature = "watchdog"))]
+    unsafe fn fmt_tag(ptr: *const (), f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let raw = Self::from_ptr(ptr);
+        crate::debug_tag::fmt_tag::<T>(raw.tag, f)
+    }
+
     /// Runs a task.
     ///
     /// If polling its future panics, the task will be closed and the panic will be propagated into
@@ -488,6 +606,8 @@ where
                 Ordering::Acquire,
             ) {
                 Ok(_) => {
+                    (*raw.header).touch();
+
                     // Update the state because we're continuing with polling the future.
                     state = (state & !SCHEDULED) | RUNNING;
                     break;
@@ -496,12 +616,46 @@ where
             }
         }
 
+        #[cfg(feature = "hooks")]
+        crate::hooks::emit(|h| h.on_poll_start(TaskId::new(&*raw.header)));
+        #[cfg(feature = "stats")]
+        let start = (*raw.header).stats.poll_start();
+
         // Poll the inner future, but surround it with a guard that closes the task in case polling
         // panics.
         let guard = Guard(raw);
-        let poll = <F as Future>::poll(Pin::new_unchecked(&mut *raw.future), cx);
+        let poll = {
+            // The guards below refer to the header, so they must end with the poll. Once the
+            // state is updated, the task may be destroyed, either below or by the panic guard.
+
+            // Let the future know which task it belongs to while it is being polled.
+            #[cfg(feature = "std")]
+            let _enter = crate::current::enter(raw.header);
+
+            // Let the watchdog know which task this thread is polling.
+            #[cfg(feature = "watchdog")]
+            let _watch = crate::watchdog::enter(raw.header);
+
+            // Enter the span of the task while its future is being polled.
+            #[cfg(feature = "tracing")]
+            let _span = (*raw.header).span.enter();
+
+            <F as Future>::poll(Pin::new_unchecked(&mut *raw.future), cx)
+        };
         mem::forget(guard);
 
+        #[cfg(feature = "stats")]
+        (*raw.header).stats.poll_end(start, poll.is_pending());
+
+        #[cfg(feature = "hooks")]
+        crate::hooks::emit(|h| {
+            let outcome = match poll {
+                Poll::Ready(_) => PollOutcome::Ready,
+                Poll::Pending => PollOutcome::Pending,
+            };
+            h.on_poll_end(TaskId::new(&*raw.header), outcome)
+        });
+
         match poll {
             Poll::Ready(out) => {
                 // Replace the future with its output.
@@ -528,6 +682,15 @@ where
                         Ordering::Acquire,
                     ) {
                         Ok(_) => {
+                            (*raw.header).touch();
+
+                            #[cfg(feature = "hooks")]
+                            crate::hooks::emit(|h| h.on_complete(TaskId::new(&*raw.header)));
+                            #[cfg(feature = "tracing")]
+                            crate::trace::complete(&*raw.header);
+                            #[cfg(feature = "metrics")]
+                            crate::metrics::complete();
+
                             // If the handle is dropped or if the task was closed while running,
                             // now it's time to drop the output.
                             if state & HANDLE == 0 || state & CLOSED != 0 {
@@ -579,6 +742,8 @@ where
                         Ordering::Acquire,
                     ) {
                         Ok(state) => {
+                            (*raw.header).touch();
+
                             // If the task was closed while running, we need to notify the awaiter.
                             // If the task was woken up while running, we need to schedule it.
                             // Otherwise, we just drop the task reference.
@@ -612,17 +777,26 @@ where
         struct Guard<F, R, S, T>(RawTask<F, R, S, T>)
         where
             F: Future<Output = R> + 'static,
-            S: Fn(Task<T>) + Send + Sync + 'static;
+            S: Fn(Task<T>) + Send + Sync + 'static,
+            T: 'static;
 
         impl<F, R, S, T> Drop for Guard<F, R, S, T>
         where
             F: Future<Output = R> + 'static,
             S: Fn(Task<T>) + Send + Sync + 'static,
+            T: 'static,
         {
             fn drop(&mut self) {
                 let raw = self.0;
                 let ptr = raw.header as *const ();
 
+                #[cfg(feature = "hooks")]
+                crate::hooks::emit(|h| unsafe {
+                    h.on_poll_end(TaskId::new(&*raw.header), PollOutcome::Panicked)
+                });
+                #[cfg(feature = "metrics")]
+                crate::metrics::panic();
+
                 unsafe {
                     let mut state = (*raw.header).state.load(Ordering::Acquire);
 
@@ -657,6 +831,8 @@ where
                             Ordering::Acquire,
                         ) {
                             Ok(state) => {
+                                (*raw.header).touch();
+
                                 // Drop the future because the task is now closed.
                                 RawTask::<F, R, S, T>::drop_future(ptr);
 
@@ -0,0 +1,506 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::fmt;
+use core::panic::Location;
+use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+
+use std::io::{self, Write};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use crate::header::Header;
+use crate::name::TaskName;
+use crate::state::*;
+
+/// The list of live tasks.
+static LIST: Mutex<List> = Mutex::new(List {
+    head: ptr::null(),
+    len: 0,
+});
+
+/// The moment timestamps are measured from.
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+/// An intrusive doubly-linked list of task headers.
+struct List {
+    /// The most recently allocated task.
+    head: *const Header,
+
+    /// Number of tasks in the list.
+    len: usize,
+}
+
+// The headers are only accessed while the list is locked.
+unsafe impl Send for List {}
+
+/// The entry of a task in the registry, stored in its header.
+pub(crate) struct Entry {
+    /// Milliseconds since the epoch at which the task entered its current state.
+    ///
+    /// The count wraps around on targets with 32-bit pointers, which is harmless because only
+    /// differences between two counts are used.
+    since: AtomicUsize,
+
+    /// The previous task in the list, protected by its lock.
+    prev: Cell<*const Header>,
+
+    /// The next task in the list, protected by its lock.
+    next: Cell<*const Header>,
+}
+
+impl Entry {
+    /// Creates an entry for a newly allocated task.
+    pub(crate) fn new() -> Entry {
+        Entry {
+            since: AtomicUsize::new(now()),
+            prev: Cell::new(ptr::null()),
+            next: Cell::new(ptr::null()),
+        }
+    }
+
+    /// Records that the task has just entered a new state.
+    #[inline]
+    pub(crate) fn touch(&self) {
+        self.since.store(now(), Ordering::Relaxed);
+    }
+}
+
+/// Returns the number of milliseconds since the epoch, wrapped to fit into a `usize`.
+fn now() -> usize {
+    let epoch = EPOCH.get_or_init(Instant::now);
+    epoch.elapsed().as_millis() as usize
+}
+
+/// Returns the time between two results of [`now()`].
+///
+/// A task may enter a new state right after `now` was taken, in which case no time has elapsed.
+///
+/// [`now()`]: fn.now.html
+fn elapsed(now: usize, since: usize) -> Duration {
+    let millis = (now.wrapping_sub(since) as isize).max(0);
+    Duration::from_millis(millis as u64)
+}
+
+/// Links a newly allocated task into the list.
+///
+/// The header must stay valid until it is unlinked.
+pub(crate) unsafe fn link(header: *const Header) {
+    let mut list = LIST.lock().unwrap_or_else(|e| e.into_inner());
+
+    (*header).entry.next.set(list.head);
+    if !list.head.is_null() {
+        (*list.head).entry.prev.set(header);
+    }
+    list.head = header;
+    list.len += 1;
+}
+
+/// Unlinks a task that is about to be deallocated from the list.
+pub(crate) unsafe fn unlink(header: *const Header) {
+    let mut list = LIST.lock().unwrap_or_else(|e| e.into_inner());
+
+    let prev = (*header).entry.prev.get();
+    let next = (*header).entry.next.get();
+    if prev.is_null() {
+        list.head = next;
+    } else {
+        (*prev).entry.next.set(next);
+    }
+    if !next.is_null() {
+        (*next).entry.prev.set(prev);
+    }
+    list.len -= 1;
+}
+
+/// Lists all live tasks, ordered by ID.
+///
+/// A task is live from the moment it is spawned until its memory is deallocated, which happens
+/// once its [`Task`], [`JoinHandle`], and all of its wakers are dropped. Tasks that are stuck
+/// waiting for a wake-up that never comes show up here as neither scheduled nor running, together
+/// with how long they have been in that state.
+///
+/// Spawning a task doesn't require its tag to implement [`Debug`], so tags are only formatted with
+/// [`Debug`] if their type has been registered with [`debug_tags()`]. Other tags are shown as the
+/// name of their type in angle brackets, like `<my_crate::Tag>`. The tags are formatted while the
+/// registry is locked, so their [`Debug`] implementations must not spawn or drop tasks.
+///
+/// **NOTE:** This function is only available when the `registry` feature for this crate is
+/// enabled.
+///
+/// [`Task`]: struct.Task.html
+/// [`JoinHandle`]: struct.JoinHandle.html
+/// [`debug_tags()`]: fn.debug_tags.html
+/// [`Debug`]: https://doc.rust-lang.org/std/fmt/trait.Debug.html
+///
+/// # Examples
+///
+/// ```
+/// let (task, handle) = async_task::spawn(async {}, |_| {}, 7u32);
+/// async_task::debug_tags::<u32>();
+///
+/// for info in async_task::dump() {
+///     println!("{}", info);
+/// }
+///
+/// let info = async_task::dump().pop().unwrap();
+/// assert!(info.is_scheduled());
+/// assert_eq!(info.tag(), "7");
+/// ```
+pub fn dump() -> Vec<TaskInfo> {
+    let now = now();
+    let list = LIST.lock().unwrap_or_else(|e| e.into_inner());
+    let mut infos = Vec::with_capacity(list.len);
+
+    let mut header = list.head;
+    while !header.is_null() {
+        // The header is valid because tasks unlink themselves before being deallocated.
+        let h = unsafe { &*header };
+
+        infos.push(TaskInfo {
+            id: h.id,
+            name: h.name().cloned(),
+            location: h.location(),
+            state: h.state.load(Ordering::Acquire),
+            tag: unsafe { crate::debug_tag::format(header) },
+            elapsed: elapsed(now, h.entry.since.load(Ordering::Relaxed)),
+        });
+        header = h.entry.next.get();
+    }
+    drop(list);
+
+    infos.sort_by_key(|info| info.id);
+    infos
+}
+
+/// A point in time to check for leaked tasks against.
+///
+/// Tasks spawned after the checkpoint was created that are still alive can be listed with
+/// [`alive()`], and the ones among them that are stuck forever with [`stuck()`]. A checkpoint at
+/// the start of a test or of `main()` finds tasks that were leaked by the time it ends, and
+/// [`report_at_exit()`] prints them when `main()` returns.
+///
+/// **NOTE:** This type is only available when the `registry` feature for this crate is enabled.
+///
+/// [`alive()`]: #method.alive
+/// [`stuck()`]: #method.stuck
+/// [`report_at_exit()`]: #method.report_at_exit
+///
+/// # Examples
+///
+/// ```
+/// use async_task::Checkpoint;
+/// use futures::future;
+///
+/// let checkpoint = Checkpoint::new();
+///
+/// // This future never registers its waker, so the task can never be woken.
+/// let (task, handle) = async_task::spawn(future::pending::<()>(), |_| {}, ());
+/// task.run();
+///
+/// let stuck = checkpoint.stuck();
+/// assert_eq!(stuck.len(), 1);
+/// eprintln!("leaked: {}", stuck[0]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Checkpoint {
+    /// The ID of the first task spawned after the checkpoint.
+    id: u64,
+}
+
+impl Checkpoint {
+    /// Creates a checkpoint.
+    pub fn new() -> Checkpoint {
+        Checkpoint {
+            id: crate::utils::peek_task_id(),
+        }
+    }
+
+    /// Lists the live tasks that were spawned after the checkpoint, ordered by ID.
+    pub fn alive(&self) -> Vec<TaskInfo> {
+        let mut infos = dump();
+        infos.retain(|info| info.id >= self.id);
+        infos
+    }
+
+    /// Lists the tasks spawned after the checkpoint that are stuck forever, ordered by ID.
+    ///
+    /// See [`TaskInfo::is_stuck()`] for what makes a task stuck.
+    ///
+    /// [`TaskInfo::is_stuck()`]: struct.TaskInfo.html#method.is_stuck
+    pub fn stuck(&self) -> Vec<TaskInfo> {
+        let mut infos = self.alive();
+        infos.retain(|info| info.is_stuck());
+        infos
+    }
+
+    /// Returns a guard that reports leaked tasks when dropped.
+    ///
+    /// When the guard is dropped, the tasks spawned after the checkpoint that are still alive are
+    /// printed to standard error, one per line, with stuck tasks marked as such. Nothing is
+    /// printed if there are none. Binding the guard to a variable at the start of `main()`
+    /// reports the tasks that are still alive when the process exits.
+    ///
+    /// The guard is not dropped if the process exits through [`process::exit()`] or a panic that
+    /// aborts.
+    ///
+    /// [`process::exit()`]: https://doc.rust-lang.org/std/process/fn.exit.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_task::Checkpoint;
+    ///
+    /// fn main() {
+    ///     let _report = Checkpoint::new().report_at_exit();
+    ///
+    ///     // Spawn and run tasks...
+    /// }
+    /// ```
+    pub fn report_at_exit(self) -> ExitReport {
+        ExitReport { checkpoint: self }
+    }
+}
+
+impl Default for Checkpoint {
+    fn default() -> Checkpoint {
+        Checkpoint::new()
+    }
+}
+
+/// Reports leaked tasks when dropped.
+///
+/// Returned by [`Checkpoint::report_at_exit()`].
+///
+/// **NOTE:** This type is only available when the `registry` feature for this crate is enabled.
+///
+/// [`Checkpoint::report_at_exit()`]: struct.Checkpoint.html#method.report_at_exit
+#[derive(Debug)]
+#[must_use = "leaked tasks are reported when the guard is dropped"]
+pub struct ExitReport {
+    /// The checkpoint to report leaked tasks against.
+    checkpoint: Checkpoint,
+}
+
+impl Drop for ExitReport {
+    fn drop(&mut self) {
+        let alive = self.checkpoint.alive();
+        if alive.is_empty() {
+            return;
+        }
+
+        // Errors are ignored because there is nowhere else to report them.
+        let stderr = io::stderr();
+        let mut out = stderr.lock();
+        let _ = writeln!(
+            out,
+            "async-task: {} task(s) still alive at exit:",
+            alive.len()
+        );
+        for info in &alive {
+            let stuck = if info.is_stuck() { " (stuck)" } else { "" };
+            let _ = writeln!(out, "  {}{}", info, stuck);
+        }
+    }
+}
+
+/// A snapshot of a live task, returned by [`dump()`].
+///
+/// **NOTE:** This type is only available when the `registry` feature for this crate is enabled.
+///
+/// [`dump()`]: fn.dump.html
+#[derive(Clone)]
+pub struct TaskInfo {
+    /// The ID of the task.
+    id: u64,
+
+    /// The name of the task.
+    name: Option<TaskName>,
+
+    /// Where the task was spawned.
+    location: &'static Location<'static>,
+
+    /// The state of the task, including the reference count.
+    state: usize,
+
+    /// The formatted tag.
+    tag: String,
+
+    /// How long the task has been in its current state.
+    elapsed: Duration,
+}
+
+impl TaskInfo {
+    /// Returns the ID of the task.
+    ///
+    /// IDs are assigned in order of allocation and never reused.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the name of the task, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the location in the source code where the task was spawned.
+    ///
+    /// This is the location of the call to [`spawn()`] or a similar function. Functions that
+    /// spawn tasks on behalf of their callers can be annotated with `#[track_caller]` to report
+    /// their own callers instead.
+    ///
+    /// [`spawn()`]: fn.spawn.html
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// Returns `true` if the task is stuck forever.
+    ///
+    /// A task is stuck when its future is pending, but it is neither scheduled nor running and no
+    /// [`Task`] or [`Waker`] reference is left that could wake it. Only the [`JoinHandle`] keeps
+    /// it alive, and awaiting the handle would never complete. This usually means that the future
+    /// dropped its waker without waking it, or returned `Poll::Pending` without storing the waker
+    /// anywhere.
+    ///
+    /// [`Task`]: struct.Task.html
+    /// [`Waker`]: https://doc.rust-lang.org/std/task/struct.Waker.html
+    /// [`JoinHandle`]: struct.JoinHandle.html
+    pub fn is_stuck(&self) -> bool {
+        self.state & (SCHEDULED | RUNNING | COMPLETED | CLOSED) == 0
+            && self.state & HANDLE != 0
+            && self.ref_count() == 0
+    }
+
+    /// Returns `true` if the task is scheduled for running.
+    pub fn is_scheduled(&self) -> bool {
+        self.state & SCHEDULED != 0
+    }
+
+    /// Returns `true` if the future of the task is being polled.
+    pub fn is_running(&self) -> bool {
+        self.state & RUNNING != 0
+    }
+
+    /// Returns `true` if the future of the task has completed.
+    pub fn is_completed(&self) -> bool {
+        self.state & COMPLETED != 0
+    }
+
+    /// Returns `true` if the task has been canceled or its output has been taken.
+    pub fn is_closed(&self) -> bool {
+        self.state & CLOSED != 0
+    }
+
+    /// Returns `true` if the [`JoinHandle`] of the task still exists.
+    ///
+    /// [`JoinHandle`]: struct.JoinHandle.html
+    pub fn has_handle(&self) -> bool {
+        self.state & HANDLE != 0
+    }
+
+    /// Returns `true` if the [`JoinHandle`] of the task is being awaited.
+    ///
+    /// [`JoinHandle`]: struct.JoinHandle.html
+    pub fn has_awaiter(&self) -> bool {
+        self.state & AWAITER != 0
+    }
+
+    /// Returns `true` if cancellation of the task has been requested.
+    pub fn is_cancel_requested(&self) -> bool {
+        self.state & CANCEL_REQUESTED != 0
+    }
+
+    /// Returns the number of [`Task`] and [`Waker`] references to the task.
+    ///
+    /// The [`JoinHandle`] is not counted, see [`has_handle()`].
+    ///
+    /// [`Task`]: struct.Task.html
+    /// [`Waker`]: https://doc.rust-lang.org/std/task/struct.Waker.html
+    /// [`JoinHandle`]: struct.JoinHandle.html
+    /// [`has_handle()`]: #method.has_handle
+    pub fn ref_count(&self) -> usize {
+        self.state / REFERENCE
+    }
+
+    /// Returns the names of the state flags that are set, such as `"SCHEDULED"` or `"CLOSED"`.
+    pub fn flags(&self) -> Vec<&'static str> {
+        FLAGS
+            .iter()
+            .filter(|(bit, _)| self.state & bit != 0)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    /// Returns the formatted tag of the task.
+    ///
+    /// This is the [`Debug`] output of the tag if its type has been registered with
+    /// [`debug_tags()`], and the name of its type in angle brackets otherwise.
+    ///
+    /// [`Debug`]: https://doc.rust-lang.org/std/fmt/trait.Debug.html
+    /// [`debug_tags()`]: fn.debug_tags.html
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Returns how long the task has been in its current state.
+    ///
+    /// The state changes when the task is scheduled, starts or stops running, completes, or gets
+    /// canceled. The time is measured in whole milliseconds.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+/// Names of the state flags.
+const FLAGS: &[(usize, &str)] = &[
+    (SCHEDULED, "SCHEDULED"),
+    (RUNNING, "RUNNING"),
+    (COMPLETED, "COMPLETED"),
+    (CLOSED, "CLOSED"),
+    (HANDLE, "HANDLE"),
+    (AWAITER, "AWAITER"),
+    (REGISTERING, "REGISTERING"),
+    (NOTIFYING, "NOTIFYING"),
+    (CANCEL_REQUESTED, "CANCEL_REQUESTED"),
+];
+
+impl fmt::Debug for TaskInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskInfo")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("location", &self.location)
+            .field("flags", &self.flags())
+            .field("ref_count", &self.ref_count())
+            .field("tag", &self.tag)
+            .field("elapsed", &self.elapsed)
+            .finish()
+    }
+}
+
+/// Formats the task as a single line, for example:
+///
+/// ```text
+/// task 3 "listener" [SCHEDULED|HANDLE] refs=1 tag=7 for 1.5ms, spawned at src/main.rs:10:5
+/// ```
+///
+/// The name is left out if the task is unnamed.
+impl fmt::Display for TaskInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task {}", self.id)?;
+        if let Some(name) = &self.name {
+            write!(f, " {:?}", name)?;
+        }
+        write!(
+            f,
+            " [{}] refs={} tag={} for {:?}, spawned at {}",
+            self.flags().join("|"),
+            self.ref_count(),
+            self.tag,
+            self.elapsed,
+            self.location
+        )
+    }
+}
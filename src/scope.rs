@@ -0,0 +1,309 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::fmt;
+use core::future::Future;
+use core::mem::{self, ManuallyDrop};
+use core::pin::Pin;
+use core::ptr;
+use core::task::{Context, Poll, Waker};
+
+use std::sync::Mutex;
+use std::thread_local;
+
+use crate::task_ref::TaskRef;
+use crate::{JoinHandle, Task};
+
+thread_local! {
+    /// The scope of the scoped task currently being polled on this thread.
+    static CURRENT: Cell<*const Scope> = const { Cell::new(ptr::null()) };
+}
+
+/// Creates a new scoped task.
+///
+/// This constructor works just like [`spawn`], except the task takes part in structured
+/// concurrency:
+///
+/// * If called while polling another scoped task, the new task becomes a child of that task.
+///   Otherwise, it becomes the root of a new task tree.
+/// * When the future of a scoped task is dropped, which happens when it completes or gets
+///   canceled by [`JoinHandle::cancel()`] or by dropping its [`Task`], all of its children are
+///   canceled too. Cancellation therefore propagates through the whole subtree.
+/// * The returned [`ScopedJoinHandle`] resolves only after the task's future and the futures of
+///   all of its descendants have been dropped.
+///
+/// **NOTE:** This function is only available when the `std` feature for this crate is enabled (it
+/// is by default).
+///
+/// [`spawn`]: fn.spawn.html
+/// [`Task`]: struct.Task.html
+/// [`JoinHandle::cancel()`]: struct.JoinHandle.html#method.cancel
+/// [`ScopedJoinHandle`]: struct.ScopedJoinHandle.html
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam::channel;
+/// use futures::future;
+///
+/// let (s, r) = channel::unbounded();
+/// let schedule = move |task| s.send(task).unwrap();
+///
+/// let (task, handle) = async_task::spawn_scoped(
+///     {
+///         let schedule = schedule.clone();
+///         async move {
+///             // This child gets canceled together with its parent.
+///             let (child, _) = async_task::spawn_scoped(future::pending::<()>(), schedule, ());
+///             child.schedule();
+///         }
+///     },
+///     schedule,
+///     (),
+/// );
+/// task.schedule();
+/// ```
+#[track_caller]
+pub fn spawn_scoped<F, R, S, T>(future: F, schedule: S, tag: T) -> (Task<T>, ScopedJoinHandle<R, T>)
+where
+    F: Future<Output = R> + Send + 'static,
+    R: Send + 'static,
+    S: Fn(Task<T>) + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+{
+    let parent = Scope::current();
+    let scope = Arc::new(Scope {
+        parent: parent.clone(),
+        inner: Mutex::new(Inner {
+            live: 1,
+            children: Vec::new(),
+            waker: None,
+        }),
+    });
+
+    let future = Scoped {
+        scope: scope.clone(),
+        future: ManuallyDrop::new(future),
+    };
+    let (task, handle) = crate::spawn(future, schedule, tag);
+
+    // Register the new task with its parent so that it can be canceled with it.
+    if let Some(parent) = parent {
+        let child = TaskRef::new(task.raw_task);
+        let mut inner = parent.inner.lock().unwrap();
+        inner.live += 1;
+        inner.children.push((scope.key(), child));
+    }
+
+    let handle = ScopedJoinHandle {
+        handle,
+        scope,
+        output: None,
+    };
+    (task, handle)
+}
+
+/// A node in a tree of scoped tasks.
+struct Scope {
+    /// The scope of the parent task.
+    parent: Option<Arc<Scope>>,
+
+    /// The mutable state of the scope.
+    inner: Mutex<Inner>,
+}
+
+struct Inner {
+    /// Number of futures in the subtree that have not been dropped yet, including the task's own.
+    live: usize,
+
+    /// Child tasks, keyed by the address of their scopes.
+    children: Vec<(usize, TaskRef)>,
+
+    /// The `ScopedJoinHandle` waiting for the subtree to finish.
+    waker: Option<Waker>,
+}
+
+impl Scope {
+    /// Returns the scope of the scoped task currently being polled.
+    fn current() -> Option<Arc<Scope>> {
+        CURRENT
+            .try_with(|current| {
+                let ptr = current.get();
+                if ptr.is_null() {
+                    None
+                } else {
+                    // The scope is kept alive by the `Scoped` future being polled.
+                    unsafe {
+                        Arc::increment_strong_count(ptr);
+                        Some(Arc::from_raw(ptr))
+                    }
+                }
+            })
+            .unwrap_or(None)
+    }
+
+    /// Returns the key identifying this scope in its parent.
+    fn key(self: &Arc<Self>) -> usize {
+        Arc::as_ptr(self) as usize
+    }
+
+    /// Cancels all children.
+    fn cancel_children(&self) {
+        let children = mem::take(&mut self.inner.lock().unwrap().children);
+
+        for (_, child) in &children {
+            child.cancel();
+        }
+    }
+
+    /// Releases one live future in the subtree.
+    ///
+    /// When the count drops to zero, the waiting handle is woken and the parent is released.
+    fn release(self: &Arc<Self>) {
+        let waker = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.live -= 1;
+            if inner.live > 0 {
+                return;
+            }
+            inner.waker.take()
+        };
+
+        if let Some(w) = waker {
+            w.wake();
+        }
+
+        if let Some(parent) = &self.parent {
+            let key = self.key();
+            let child = {
+                let mut inner = parent.inner.lock().unwrap();
+                let index = inner.children.iter().position(|(k, _)| *k == key);
+                index.map(|i| inner.children.swap_remove(i))
+            };
+
+            // Drop the task reference outside of the lock.
+            drop(child);
+            parent.release();
+        }
+    }
+}
+
+/// A future that runs inside a scope.
+struct Scoped<F> {
+    /// The scope of the task.
+    scope: Arc<Scope>,
+
+    /// The inner future.
+    future: ManuallyDrop<F>,
+}
+
+impl<F: Future> Future for Scoped<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        /// Restores the previous scope when dropped.
+        struct Restore(*const Scope);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT.with(|current| current.set(self.0));
+            }
+        }
+
+        let this = unsafe { self.get_unchecked_mut() };
+        let _restore = CURRENT.with(|current| Restore(current.replace(Arc::as_ptr(&this.scope))));
+
+        unsafe { Pin::new_unchecked(&mut *this.future).poll(cx) }
+    }
+}
+
+impl<F> Drop for Scoped<F> {
+    fn drop(&mut self) {
+        // Drop the inner future first, then cancel the children that are still running.
+        unsafe {
+            ManuallyDrop::drop(&mut self.future);
+        }
+
+        self.scope.cancel_children();
+        self.scope.release();
+    }
+}
+
+/// A handle that awaits the result of a scoped task.
+///
+/// This type works just like [`JoinHandle`], except it resolves only after the futures of the task
+/// and all of its descendants have been dropped.
+///
+/// **NOTE:** This type is only available when the `std` feature for this crate is enabled (it is
+/// by default).
+///
+/// [`JoinHandle`]: struct.JoinHandle.html
+pub struct ScopedJoinHandle<R, T> {
+    /// The handle of the task itself.
+    handle: JoinHandle<R, T>,
+
+    /// The scope of the task.
+    scope: Arc<Scope>,
+
+    /// The output of the task, stored while waiting for its descendants.
+    output: Option<Option<R>>,
+}
+
+impl<R, T> Unpin for ScopedJoinHandle<R, T> {}
+
+impl<R, T> ScopedJoinHandle<R, T> {
+    /// Cancels the task and all of its descendants.
+    ///
+    /// If the task has already completed, calling this method will have no effect.
+    pub fn cancel(&self) {
+        self.handle.cancel();
+    }
+
+    /// Returns a reference to the tag stored inside the task.
+    pub fn tag(&self) -> &T {
+        self.handle.tag()
+    }
+
+    /// Returns a waker associated with the task.
+    pub fn waker(&self) -> Waker {
+        self.handle.waker()
+    }
+}
+
+impl<R, T> Future for ScopedJoinHandle<R, T> {
+    type Output = Option<R>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        if this.output.is_none() {
+            match Pin::new(&mut this.handle).poll(cx) {
+                Poll::Ready(output) => this.output = Some(output),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        // Wait until all futures in the subtree have been dropped.
+        {
+            let mut inner = this.scope.inner.lock().unwrap();
+            if inner.live > 0 {
+                inner.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+        }
+
+        Poll::Ready(
+            this.output
+                .take()
+                .expect("`ScopedJoinHandle` polled after completion"),
+        )
+    }
+}
+
+impl<R, T> fmt::Debug for ScopedJoinHandle<R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScopedJoinHandle")
+            .field("handle", &self.handle)
+            .finish()
+    }
+}
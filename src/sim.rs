@@ -0,0 +1,338 @@
+//! Deterministic simulation for testing.
+//!
+//! A [`Simulation`] runs tasks on a single thread in an order chosen by a seeded pseudo-random
+//! number generator. When a task of the simulation is woken, the wake is delivered at a random
+//! point too, and timers use a manual [`Clock`] that jumps forward whenever there is nothing else
+//! to do.
+//!
+//! As a result, every run with the same seed makes the same choices, so a failing seed can be
+//! replayed exactly. Running a test over many seeds explores different interleavings of tasks,
+//! wakes, cancellation, and completion.
+//!
+//! Only wakes of tasks spawned on the simulation are delayed. Wakers made with [`waker_fn()`] are
+//! deliberately left alone, because intercepting them would also reorder wakes meant for other
+//! executors and threads that share the process with the simulation.
+//!
+//! **NOTE:** This module is only available when the `sim` feature for this crate is enabled.
+//!
+//! [`Simulation`]: struct.Simulation.html
+//! [`Clock`]: ../timer/struct.Clock.html
+//! [`waker_fn()`]: ../fn.waker_fn.html
+//!
+//! # Examples
+//!
+//! ```
+//! use async_task::sim::Simulation;
+//! use std::time::Duration;
+//!
+//! let sim = Simulation::new(42);
+//! let clock = sim.clock();
+//!
+//! let handle = sim.spawn(async move {
+//!     clock.sleep(Duration::from_secs(60)).await;
+//!     clock.now()
+//! });
+//!
+//! // No real time passes, but the virtual clock moves forward by a minute.
+//! assert_eq!(sim.run_until(handle), Some(Duration::from_secs(60)));
+//! ```
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll};
+use core::time::Duration;
+
+use std::sync::Mutex;
+
+use crate::timer::Clock;
+use crate::{JoinHandle, LocalSpawn, Task};
+
+/// A deterministic single-threaded executor.
+///
+/// See the [module-level documentation] for details.
+///
+/// The simulation is driven by [`step()`], [`run()`], or [`run_until()`].
+///
+/// [module-level documentation]: index.html
+/// [`step()`]: #method.step
+/// [`run()`]: #method.run
+/// [`run_until()`]: #method.run_until
+pub struct Simulation {
+    /// The seed the simulation was created with.
+    seed: u64,
+
+    /// State shared with the schedule functions.
+    shared: Arc<Shared>,
+
+    /// Makes the type `!Send` and `!Sync`.
+    _marker: PhantomData<*mut ()>,
+}
+
+/// State shared with the schedule functions.
+struct Shared {
+    /// State of the pseudo-random number generator.
+    rng: Mutex<u64>,
+
+    /// Tasks ready to be run.
+    runnable: Mutex<Vec<Task<()>>>,
+
+    /// Tasks that were woken, but whose wakes haven't been delivered yet.
+    woken: Mutex<Vec<Task<()>>>,
+
+    /// The virtual clock.
+    clock: Clock,
+}
+
+/// An event the simulation can pick next.
+enum Event {
+    /// Run a task.
+    Run(Task<()>),
+
+    /// Deliver the wake of a task, which makes it runnable.
+    Wake(Task<()>),
+}
+
+impl Shared {
+    /// Returns the next pseudo-random number.
+    fn next_u64(&self) -> u64 {
+        // xorshift64*
+        let mut rng = self.rng.lock().unwrap();
+        let mut x = *rng;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        *rng = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Picks the next event at random.
+    ///
+    /// `extra` is the number of additional events the caller is able to pick. If one of those is
+    /// chosen, `Err(index)` is returned. Returns `None` if there are no events.
+    fn pick(&self, extra: usize) -> Option<Result<Event, usize>> {
+        let tasks = self.runnable.lock().unwrap().len();
+        let wakes = self.woken.lock().unwrap().len();
+        let total = tasks + wakes + extra;
+
+        if total == 0 {
+            return None;
+        }
+
+        let i = (self.next_u64() % total as u64) as usize;
+        let event = if i < tasks {
+            Ok(Event::Run(self.runnable.lock().unwrap().swap_remove(i)))
+        } else if i < tasks + wakes {
+            Ok(Event::Wake(
+                self.woken.lock().unwrap().swap_remove(i - tasks),
+            ))
+        } else {
+            Err(i - tasks - wakes)
+        };
+        Some(event)
+    }
+
+    /// Advances the clock to the earliest timer, which fires the timers due at that time.
+    ///
+    /// Returns `false` if there are no timers.
+    fn advance(&self) -> bool {
+        match self.clock.next_deadline() {
+            None => false,
+            Some(deadline) => {
+                self.clock.advance(deadline - self.clock.now());
+                true
+            }
+        }
+    }
+}
+
+impl Simulation {
+    /// Creates a new simulation on the current thread.
+    ///
+    /// Simulations created with the same seed make the same choices.
+    pub fn new(seed: u64) -> Simulation {
+        // Scramble the seed with splitmix64 because xorshift gets stuck on zero.
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        Simulation {
+            seed,
+            shared: Arc::new(Shared {
+                rng: Mutex::new(if z == 0 { 1 } else { z }),
+                runnable: Mutex::new(Vec::new()),
+                woken: Mutex::new(Vec::new()),
+                clock: Clock::manual(),
+            }),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the seed this simulation was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the virtual clock of this simulation.
+    ///
+    /// This is a manual [`Clock`], which the simulation advances to the next timer whenever it
+    /// has nothing else to do. Instants are measured as the time elapsed since the simulation was
+    /// created.
+    ///
+    /// [`Clock`]: ../timer/struct.Clock.html
+    pub fn clock(&self) -> Clock {
+        self.shared.clock.clone()
+    }
+
+    /// Returns the time elapsed on the virtual clock since the simulation was created.
+    pub fn now(&self) -> Duration {
+        self.shared.clock.now()
+    }
+
+    /// Returns a pseudo-random number from the simulation's generator.
+    ///
+    /// This is useful for making other random choices in a test reproducible with the same seed.
+    pub fn random(&self) -> u64 {
+        self.shared.next_u64()
+    }
+
+    /// Spawns a future onto the simulation.
+    ///
+    /// The future does not need to implement [`Send`] because it only ever runs on the
+    /// simulation's thread.
+    ///
+    /// [`Send`]: https://doc.rust-lang.org/std/marker/trait.Send.html
+    #[track_caller]
+    pub fn spawn<F, R>(&self, future: F) -> JoinHandle<R, ()>
+    where
+        F: Future<Output = R> + 'static,
+        R: 'static,
+    {
+        // Scheduled tasks only become runnable once the simulation delivers their wake.
+        let shared = self.shared.clone();
+        let schedule = move |task| shared.woken.lock().unwrap().push(task);
+        let (task, handle) = crate::spawn_local(future, schedule, ());
+        self.shared.runnable.lock().unwrap().push(task);
+        handle
+    }
+
+    /// Runs a single randomly chosen task or delivers a single wake.
+    ///
+    /// If there is nothing else to do, the virtual clock is advanced to the next timer first.
+    /// Returns `false` if the simulation is idle, meaning there are no tasks, wakes, or timers.
+    pub fn step(&self) -> bool {
+        loop {
+            match self.shared.pick(0) {
+                // Nothing to do, so jump to the next timer and try again.
+                None if self.shared.advance() => {}
+                None => return false,
+                Some(Ok(event)) => {
+                    self.handle(event);
+                    return true;
+                }
+                Some(Err(_)) => unreachable!(),
+            }
+        }
+    }
+
+    /// Runs the simulation until it is idle.
+    pub fn run(&self) {
+        while self.step() {}
+    }
+
+    /// Runs the simulation until `future` completes, and returns its output.
+    ///
+    /// Polling the future is one of the events picked at random whenever it is woken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the simulation becomes idle before the future completes, which means the future
+    /// can never complete.
+    pub fn run_until<F: Future>(&self, future: F) -> F::Output {
+        // Pin the future on the stack. It is shadowed so that it can't be moved afterwards.
+        let mut future = future;
+        let mut future = unsafe { Pin::new_unchecked(&mut future) };
+
+        // Set when the future is woken, and initially so that it gets polled.
+        let woken = Arc::new(AtomicBool::new(true));
+        let waker = {
+            let woken = woken.clone();
+            crate::waker_fn(move || woken.store(true, Ordering::SeqCst))
+        };
+        let cx = &mut Context::from_waker(&waker);
+
+        loop {
+            let extra = woken.load(Ordering::SeqCst) as usize;
+
+            match self.shared.pick(extra) {
+                // Nothing to do, so jump to the next timer, which may also wake the future.
+                None if self.shared.advance() => {}
+                None => panic!("simulation with seed {} deadlocked", self.seed),
+                Some(Ok(event)) => self.handle(event),
+                Some(Err(_)) => {
+                    woken.store(false, Ordering::SeqCst);
+                    if let Poll::Ready(output) = future.as_mut().poll(cx) {
+                        return output;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handles an event picked by the simulation.
+    fn handle(&self, event: Event) {
+        match event {
+            Event::Run(task) => {
+                task.run();
+            }
+            Event::Wake(task) => self.shared.runnable.lock().unwrap().push(task),
+        }
+    }
+}
+
+impl LocalSpawn for Simulation {
+    type Tag = ();
+
+    #[track_caller]
+    fn spawn_local<F>(&self, future: F) -> JoinHandle<F::Output, ()>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        self.spawn(future)
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        // The timers hold wakers of tasks, which would otherwise keep the simulation alive. Tasks
+        // whose last waker is dropped get scheduled once more so that their futures are dropped.
+        self.shared.clock.clear();
+
+        // Cancel the scheduled tasks on this thread, which is where their futures must be dropped.
+        loop {
+            let task = self.shared.runnable.lock().unwrap().pop();
+            let task = task.or_else(|| self.shared.woken.lock().unwrap().pop());
+            match task {
+                None => break,
+                Some(task) => drop(task),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Simulation")
+            .field("seed", &self.seed)
+            .field("now", &self.now())
+            .field("runnable", &self.shared.runnable.lock().unwrap().len())
+            .field("woken", &self.shared.woken.lock().unwrap().len())
+            .finish()
+    }
+}
@@ -0,0 +1,195 @@
+use alloc::sync::Arc;
+use core::fmt;
+use core::future::Future;
+
+use crate::{JoinHandle, Task};
+
+/// An executor that can spawn futures implementing [`Send`].
+///
+/// Library code can be generic over this trait to spawn tasks onto whichever executor the
+/// application uses, instead of hardcoding a schedule function.
+///
+/// Any schedule function can be turned into a spawner with [`Spawner`].
+///
+/// [`Send`]: https://doc.rust-lang.org/std/marker/trait.Send.html
+/// [`Spawner`]: struct.Spawner.html
+///
+/// # Examples
+///
+/// ```
+/// use async_task::{JoinHandle, Spawn, Spawner};
+///
+/// // Library code that doesn't care which executor it runs on.
+/// fn start<S: Spawn>(spawner: &S) -> JoinHandle<i32, S::Tag> {
+///     spawner.spawn(async { 1 + 2 })
+/// }
+///
+/// let (s, r) = crossbeam::channel::unbounded();
+/// let spawner = Spawner::new(move |task| s.send(task).unwrap());
+///
+/// let handle = start(&spawner);
+/// r.recv().unwrap().run();
+/// ```
+pub trait Spawn {
+    /// The tag of spawned tasks.
+    type Tag;
+
+    /// Spawns a future and schedules it for running.
+    #[track_caller]
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output, Self::Tag>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static;
+}
+
+/// An executor that can spawn futures not implementing [`Send`].
+///
+/// This is the counterpart of [`Spawn`] for executors that run tasks on the thread they were
+/// spawned on.
+///
+/// [`Send`]: https://doc.rust-lang.org/std/marker/trait.Send.html
+/// [`Spawn`]: trait.Spawn.html
+pub trait LocalSpawn {
+    /// The tag of spawned tasks.
+    type Tag;
+
+    /// Spawns a local future and schedules it for running.
+    #[track_caller]
+    fn spawn_local<F>(&self, future: F) -> JoinHandle<F::Output, Self::Tag>
+    where
+        F: Future + 'static,
+        F::Output: 'static;
+}
+
+impl<E: Spawn + ?Sized> Spawn for &E {
+    type Tag = E::Tag;
+
+    #[track_caller]
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output, E::Tag>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        (**self).spawn(future)
+    }
+}
+
+impl<E: Spawn + ?Sized> Spawn for Arc<E> {
+    type Tag = E::Tag;
+
+    #[track_caller]
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output, E::Tag>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        (**self).spawn(future)
+    }
+}
+
+impl<E: LocalSpawn + ?Sized> LocalSpawn for &E {
+    type Tag = E::Tag;
+
+    #[track_caller]
+    fn spawn_local<F>(&self, future: F) -> JoinHandle<F::Output, E::Tag>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        (**self).spawn_local(future)
+    }
+}
+
+/// A spawner built from a schedule function.
+///
+/// Every spawned task gets a clone of the tag and invokes the shared schedule function when woken.
+/// The task is scheduled right after being spawned.
+///
+/// Spawning local futures through [`LocalSpawn`] requires the `std` feature.
+///
+/// [`LocalSpawn`]: trait.LocalSpawn.html
+pub struct Spawner<S, T = ()> {
+    /// The schedule function shared by all spawned tasks.
+    schedule: Arc<S>,
+
+    /// The tag given to spawned tasks.
+    tag: T,
+}
+
+impl<S> Spawner<S> {
+    /// Creates a spawner from a schedule function.
+    pub fn new(schedule: S) -> Spawner<S> {
+        Spawner::with_tag(schedule, ())
+    }
+}
+
+impl<S, T> Spawner<S, T> {
+    /// Creates a spawner from a schedule function, giving every spawned task a clone of `tag`.
+    pub fn with_tag(schedule: S, tag: T) -> Spawner<S, T> {
+        Spawner {
+            schedule: Arc::new(schedule),
+            tag,
+        }
+    }
+
+    /// Returns the tag given to spawned tasks.
+    pub fn tag(&self) -> &T {
+        &self.tag
+    }
+}
+
+impl<S, T> Spawn for Spawner<S, T>
+where
+    S: Fn(Task<T>) + Send + Sync + 'static,
+    T: Clone + Send + Sync + 'static,
+{
+    type Tag = T;
+
+    #[track_caller]
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output, T>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let schedule = self.schedule.clone();
+        let (task, handle) = crate::spawn(future, move |t| schedule(t), self.tag.clone());
+        task.schedule();
+        handle
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S, T> LocalSpawn for Spawner<S, T>
+where
+    S: Fn(Task<T>) + Send + Sync + 'static,
+    T: Clone + Send + Sync + 'static,
+{
+    type Tag = T;
+
+    #[track_caller]
+    fn spawn_local<F>(&self, future: F) -> JoinHandle<F::Output, T>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        let schedule = self.schedule.clone();
+        let (task, handle) = crate::spawn_local(future, move |t| schedule(t), self.tag.clone());
+        task.schedule();
+        handle
+    }
+}
+
+impl<S, T: Clone> Clone for Spawner<S, T> {
+    fn clone(&self) -> Spawner<S, T> {
+        Spawner {
+            schedule: self.schedule.clone(),
+            tag: self.tag.clone(),
+        }
+    }
+}
+
+impl<S, T: fmt::Debug> fmt::Debug for Spawner<S, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Spawner").field("tag", &self.tag).finish()
+    }
+}
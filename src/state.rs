@@ -59,6 +59,13 @@ pub(crate) const REGISTERING: usize = 1 << 6;
 /// notified, whichever side came first will take over the reposibility of resolving the race.
 pub(crate) const NOTIFYING: usize = 1 << 7;
 
+/// Set if cancellation of the task has been requested.
+///
+/// This flag is set by `JoinHandle::request_cancel()`. Unlike `CLOSED`, it does not prevent the
+/// future from being polled. Instead, the future can observe it and finish gracefully before it
+/// gets canceled for real.
+pub(crate) const CANCEL_REQUESTED: usize = 1 << 8;
+
 /// A single reference.
 ///
 /// The lower bits in the state contain various flags representing the task state, while the upper
@@ -67,4 +74,4 @@ pub(crate) const NOTIFYING: usize = 1 << 7;
 ///
 /// Note that the reference counter only tracks the `Task` and `Waker`s. The `JoinHandle` is
 /// tracked separately by the `HANDLE` flag.
-pub(crate) const REFERENCE: usize = 1 << 8;
+pub(crate) const REFERENCE: usize = 1 << 9;
@@ -0,0 +1,397 @@
+use core::fmt;
+use core::mem;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+use core::time::Duration;
+
+use crate::header::Header;
+
+/// The clock installed with [`set_stats_clock()`], or null if none was installed.
+static CLOCK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Marks a timestamp that is not set.
+const NONE: u64 = u64::MAX;
+
+/// Redundant wakes of a single task after which a warning is emitted, or zero if disabled.
+#[cfg(feature = "tracing")]
+static WAKE_STORM_THRESHOLD: AtomicU64 = AtomicU64::new(0);
+
+/// Installs the clock used for task statistics.
+///
+/// The clock returns the time elapsed since an arbitrary fixed moment, and must never go
+/// backwards. It can only be installed once, and should be installed before any task is spawned
+/// because timestamps taken by different clocks can't be compared.
+///
+/// When the `std` feature is enabled, [`Instant`] is used by default. Otherwise, no time passes
+/// until a clock is installed, so only the counts in [`TaskStats`] are meaningful.
+///
+/// **NOTE:** This function is only available when the `stats` feature for this crate is enabled.
+///
+/// [`Instant`]: https://doc.rust-lang.org/std/time/struct.Instant.html
+/// [`TaskStats`]: struct.TaskStats.html
+///
+/// # Errors
+///
+/// Returns an error if a clock has already been installed.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use std::time::Duration;
+///
+/// static TICKS: AtomicU64 = AtomicU64::new(0);
+///
+/// async_task::set_stats_clock(|| Duration::from_micros(TICKS.load(Ordering::Relaxed))).unwrap();
+/// ```
+pub fn set_stats_clock(now: fn() -> Duration) -> Result<(), SetStatsClockError> {
+    CLOCK
+        .compare_exchange(
+            ptr::null_mut(),
+            now as *mut (),
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        )
+        .map(|_| ())
+        .map_err(|_| SetStatsClockError(()))
+}
+
+/// Sets the number of redundant wakes after which a task is reported as a wake storm.
+///
+/// A wake is redundant when the task is already scheduled, so it has no effect. A future that
+/// keeps waking itself, or many wakers firing at a task that is waiting to run, show up as a
+/// growing number of [redundant wakes]. When a task reaches the threshold, a `WARN` event naming
+/// the task and where it was spawned is emitted once inside the task's span.
+///
+/// The warning is disabled by default, and setting the threshold to zero disables it again.
+///
+/// **NOTE:** This function is only available when the `stats` and `tracing` features for this
+/// crate are enabled.
+///
+/// [redundant wakes]: struct.TaskStats.html#method.redundant_wakes
+///
+/// # Examples
+///
+/// ```
+/// async_task::set_wake_storm_threshold(100);
+/// ```
+#[cfg(feature = "tracing")]
+pub fn set_wake_storm_threshold(threshold: u64) {
+    WAKE_STORM_THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
+/// The error returned by [`set_stats_clock()`] if a clock has already been installed.
+///
+/// **NOTE:** This type is only available when the `stats` feature for this crate is enabled.
+///
+/// [`set_stats_clock()`]: fn.set_stats_clock.html
+#[derive(Debug)]
+pub struct SetStatsClockError(());
+
+impl fmt::Display for SetStatsClockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the stats clock has already been installed")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SetStatsClockError {}
+
+/// Returns the current time in nanoseconds.
+#[inline]
+fn now() -> u64 {
+    let clock = CLOCK.load(Ordering::Acquire);
+
+    let elapsed = if clock.is_null() {
+        default_clock()
+    } else {
+        // The pointer was created from a function of this type in `set_stats_clock()`.
+        let clock = unsafe { mem::transmute::<*mut (), fn() -> Duration>(clock) };
+        clock()
+    };
+    elapsed.as_nanos() as u64
+}
+
+/// The clock used when none has been installed.
+#[cfg(feature = "std")]
+fn default_clock() -> Duration {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed()
+}
+
+/// The clock used when none has been installed.
+#[cfg(not(feature = "std"))]
+fn default_clock() -> Duration {
+    Duration::from_secs(0)
+}
+
+/// Statistics of a task, stored in its header.
+///
+/// Counters are updated with relaxed atomics by whichever thread wakes or runs the task, so a
+/// snapshot taken while the task is running may be slightly inconsistent.
+pub(crate) struct Stats {
+    /// Number of times the future was polled.
+    polls: AtomicU64,
+
+    /// Total time spent polling the future, in nanoseconds.
+    poll_total: AtomicU64,
+
+    /// Longest single poll, in nanoseconds.
+    poll_max: AtomicU64,
+
+    /// Number of times a waker of the task was woken.
+    wakes: AtomicU64,
+
+    /// Number of wakes while the task was already scheduled.
+    redundant_wakes: AtomicU64,
+
+    /// Number of wakes after the task was completed or closed.
+    stale_wakes: AtomicU64,
+
+    /// Number of wakes while the future was being polled.
+    running_wakes: AtomicU64,
+
+    /// When the task was last scheduled.
+    scheduled_at: AtomicU64,
+
+    /// Total time between being scheduled and starting to run, in nanoseconds.
+    latency_total: AtomicU64,
+
+    /// Longest time between being scheduled and starting to run, in nanoseconds.
+    latency_max: AtomicU64,
+
+    /// When the task last went idle, or `NONE` if it is not idle.
+    idle_since: AtomicU64,
+
+    /// Total time spent waiting for a wake-up, in nanoseconds.
+    idle_total: AtomicU64,
+}
+
+impl Stats {
+    /// Creates the statistics of a newly spawned task, which starts out scheduled.
+    pub(crate) fn new() -> Stats {
+        Stats {
+            polls: AtomicU64::new(0),
+            poll_total: AtomicU64::new(0),
+            poll_max: AtomicU64::new(0),
+            wakes: AtomicU64::new(0),
+            redundant_wakes: AtomicU64::new(0),
+            stale_wakes: AtomicU64::new(0),
+            running_wakes: AtomicU64::new(0),
+            scheduled_at: AtomicU64::new(now()),
+            latency_total: AtomicU64::new(0),
+            latency_max: AtomicU64::new(0),
+            idle_since: AtomicU64::new(NONE),
+            idle_total: AtomicU64::new(0),
+        }
+    }
+
+    /// Records that a waker of the task was woken.
+    #[inline]
+    pub(crate) fn wake(&self) {
+        self.wakes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a wake had no effect because the task was already scheduled.
+    ///
+    /// Warns about a wake storm when the task reaches the threshold.
+    #[inline]
+    pub(crate) fn redundant_wake(&self, header: &Header) {
+        let count = self.redundant_wakes.fetch_add(1, Ordering::Relaxed) + 1;
+
+        #[cfg(feature = "tracing")]
+        {
+            if count == WAKE_STORM_THRESHOLD.load(Ordering::Relaxed) {
+                crate::trace::wake_storm(header, count);
+            }
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = (header, count);
+    }
+
+    /// Records that a wake had no effect because the task was completed or closed.
+    #[inline]
+    pub(crate) fn stale_wake(&self) {
+        self.stale_wakes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that the task was woken while its future was being polled.
+    #[inline]
+    pub(crate) fn running_wake(&self) {
+        self.running_wakes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that the task was passed to its schedule function.
+    #[inline]
+    pub(crate) fn schedule(&self) {
+        let now = now();
+
+        let idle_since = self.idle_since.swap(NONE, Ordering::Relaxed);
+        if idle_since != NONE {
+            self.idle_total
+                .fetch_add(now.saturating_sub(idle_since), Ordering::Relaxed);
+        }
+        self.scheduled_at.store(now, Ordering::Relaxed);
+    }
+
+    /// Records that polling the future is about to start, and returns the current time.
+    #[inline]
+    pub(crate) fn poll_start(&self) -> u64 {
+        let now = now();
+
+        let latency = now.saturating_sub(self.scheduled_at.load(Ordering::Relaxed));
+        self.latency_total.fetch_add(latency, Ordering::Relaxed);
+        self.latency_max.fetch_max(latency, Ordering::Relaxed);
+        now
+    }
+
+    /// Records that polling the future that started at `start` has finished.
+    ///
+    /// If the future is still pending, the task is considered idle until it gets scheduled again.
+    #[inline]
+    pub(crate) fn poll_end(&self, start: u64, pending: bool) {
+        let now = now();
+
+        let duration = now.saturating_sub(start);
+        self.polls.fetch_add(1, Ordering::Relaxed);
+        self.poll_total.fetch_add(duration, Ordering::Relaxed);
+        self.poll_max.fetch_max(duration, Ordering::Relaxed);
+
+        if pending {
+            self.idle_since.store(now, Ordering::Relaxed);
+        }
+    }
+
+    /// Takes a snapshot of the statistics.
+    pub(crate) fn snapshot(&self) -> TaskStats {
+        let idle_since = self.idle_since.load(Ordering::Relaxed);
+        let idle_now = if idle_since == NONE {
+            0
+        } else {
+            now().saturating_sub(idle_since)
+        };
+
+        TaskStats {
+            polls: self.polls.load(Ordering::Relaxed),
+            poll_total: self.poll_total.load(Ordering::Relaxed),
+            poll_max: self.poll_max.load(Ordering::Relaxed),
+            wakes: self.wakes.load(Ordering::Relaxed),
+            redundant_wakes: self.redundant_wakes.load(Ordering::Relaxed),
+            stale_wakes: self.stale_wakes.load(Ordering::Relaxed),
+            running_wakes: self.running_wakes.load(Ordering::Relaxed),
+            latency_total: self.latency_total.load(Ordering::Relaxed),
+            latency_max: self.latency_max.load(Ordering::Relaxed),
+            idle_total: self.idle_total.load(Ordering::Relaxed) + idle_now,
+        }
+    }
+}
+
+/// A snapshot of the statistics of a task.
+///
+/// Returned by [`Task::stats()`] and [`JoinHandle::stats()`]. Durations are measured with the
+/// clock installed by [`set_stats_clock()`].
+///
+/// **NOTE:** This type is only available when the `stats` feature for this crate is enabled.
+///
+/// [`Task::stats()`]: struct.Task.html#method.stats
+/// [`JoinHandle::stats()`]: struct.JoinHandle.html#method.stats
+/// [`set_stats_clock()`]: fn.set_stats_clock.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TaskStats {
+    /// Number of times the future was polled.
+    polls: u64,
+
+    /// Total time spent polling the future, in nanoseconds.
+    poll_total: u64,
+
+    /// Longest single poll, in nanoseconds.
+    poll_max: u64,
+
+    /// Number of times a waker of the task was woken.
+    wakes: u64,
+
+    /// Number of wakes while the task was already scheduled.
+    redundant_wakes: u64,
+
+    /// Number of wakes after the task was completed or closed.
+    stale_wakes: u64,
+
+    /// Number of wakes while the future was being polled.
+    running_wakes: u64,
+
+    /// Total time between being scheduled and starting to run, in nanoseconds.
+    latency_total: u64,
+
+    /// Longest time between being scheduled and starting to run, in nanoseconds.
+    latency_max: u64,
+
+    /// Total time spent waiting for a wake-up, in nanoseconds.
+    idle_total: u64,
+}
+
+impl TaskStats {
+    /// Returns the number of times the future was polled.
+    pub fn polls(&self) -> u64 {
+        self.polls
+    }
+
+    /// Returns the total time spent polling the future.
+    pub fn total_poll_time(&self) -> Duration {
+        Duration::from_nanos(self.poll_total)
+    }
+
+    /// Returns the longest time a single poll of the future took.
+    ///
+    /// A long poll means that the future blocked the thread running it.
+    pub fn max_poll_time(&self) -> Duration {
+        Duration::from_nanos(self.poll_max)
+    }
+
+    /// Returns the number of times a waker of the task was woken.
+    pub fn wakes(&self) -> u64 {
+        self.wakes
+    }
+
+    /// Returns the number of wakes that had no effect because the task was already scheduled.
+    ///
+    /// A large number of redundant wakes is a sign of a busy loop, like a future that wakes
+    /// itself every time it is polled. See [`set_wake_storm_threshold()`] for getting warned about
+    /// such tasks.
+    ///
+    /// [`set_wake_storm_threshold()`]: fn.set_wake_storm_threshold.html
+    pub fn redundant_wakes(&self) -> u64 {
+        self.redundant_wakes
+    }
+
+    /// Returns the number of wakes that had no effect because the task was completed or closed.
+    pub fn stale_wakes(&self) -> u64 {
+        self.stale_wakes
+    }
+
+    /// Returns the number of wakes that arrived while the future was being polled.
+    ///
+    /// Such a wake schedules the task again as soon as the poll ends.
+    pub fn wakes_while_running(&self) -> u64 {
+        self.running_wakes
+    }
+
+    /// Returns the total time the task spent scheduled but waiting to be run.
+    pub fn total_schedule_latency(&self) -> Duration {
+        Duration::from_nanos(self.latency_total)
+    }
+
+    /// Returns the longest time the task spent scheduled but waiting to be run.
+    pub fn max_schedule_latency(&self) -> Duration {
+        Duration::from_nanos(self.latency_max)
+    }
+
+    /// Returns the total time the task spent waiting to be woken.
+    ///
+    /// This includes the time the task has been waiting so far, if it is waiting right now.
+    pub fn idle_time(&self) -> Duration {
+        Duration::from_nanos(self.idle_total)
+    }
+}
@@ -2,14 +2,14 @@ use core::fmt;
 use core::future::Future;
 use core::marker::PhantomData;
 use core::mem;
+use core::panic::Location;
 use core::ptr::NonNull;
 use core::sync::atomic::Ordering;
 use core::task::Waker;
 
 use crate::header::Header;
-use crate::raw::RawTask;
 use crate::state::*;
-use crate::JoinHandle;
+use crate::{Builder, JoinHandle, TaskName};
 
 /// Creates a new task.
 ///
@@ -47,6 +47,7 @@ use crate::JoinHandle;
 /// // Create a task with the future and the schedule function.
 /// let (task, handle) = async_task::spawn(future, schedule, ());
 /// ```
+#[track_caller]
 pub fn spawn<F, R, S, T>(future: F, schedule: S, tag: T) -> (Task<T>, JoinHandle<R, T>)
 where
     F: Future<Output = R> + Send + 'static,
@@ -54,23 +55,43 @@ where
     S: Fn(Task<T>) + Send + Sync + 'static,
     T: Send + Sync + 'static,
 {
-    // Allocate large futures on the heap.
-    let raw_task = if mem::size_of::<F>() >= 2048 {
-        let future = alloc::boxed::Box::pin(future);
-        RawTask::<_, R, S, T>::allocate(future, schedule, tag)
-    } else {
-        RawTask::<F, R, S, T>::allocate(future, schedule, tag)
-    };
-
-    let task = Task {
-        raw_task,
-        _marker: PhantomData,
-    };
-    let handle = JoinHandle {
-        raw_task,
-        _marker: PhantomData,
-    };
-    (task, handle)
+    Builder::new().spawn(future, schedule, tag)
+}
+
+/// Creates a new named task.
+///
+/// This is like [`spawn`], but gives the task a name, which shows up in its [`Debug`] output, in
+/// task dumps, and in tracing spans. Names can also be set with [`Builder::name()`].
+///
+/// [`spawn`]: fn.spawn.html
+/// [`Debug`]: https://doc.rust-lang.org/std/fmt/trait.Debug.html
+/// [`Builder::name()`]: struct.Builder.html#method.name
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam::channel;
+///
+/// let (s, r) = channel::unbounded();
+/// let schedule = move |task| s.send(task).unwrap();
+///
+/// let (task, handle) = async_task::spawn_named("greeter", async {}, schedule, ());
+/// assert_eq!(task.name(), Some("greeter"));
+/// ```
+#[track_caller]
+pub fn spawn_named<F, R, S, T>(
+    name: impl Into<TaskName>,
+    future: F,
+    schedule: S,
+    tag: T,
+) -> (Task<T>, JoinHandle<R, T>)
+where
+    F: Future<Output = R> + Send + 'static,
+    R: Send + 'static,
+    S: Fn(Task<T>) + Send + Sync + 'static,
+    T: Send + Sync + 'static,
+{
+    Builder::new().name(name).spawn(future, schedule, tag)
 }
 
 /// Creates a new local task.
@@ -113,6 +134,7 @@ where
 /// let (task, handle) = async_task::spawn_local(future, schedule, ());
 /// ```
 #[cfg(feature = "std")]
+#[track_caller]
 pub fn spawn_local<F, R, S, T>(future: F, schedule: S, tag: T) -> (Task<T>, JoinHandle<R, T>)
 where
     F: Future<Output = R> + 'static,
@@ -120,75 +142,7 @@ where
     S: Fn(Task<T>) + Send + Sync + 'static,
     T: Send + Sync + 'static,
 {
-    extern crate std;
-
-    use std::mem::ManuallyDrop;
-    use std::pin::Pin;
-    use std::task::{Context, Poll};
-    use std::thread::{self, ThreadId};
-    use std::thread_local;
-
-    #[inline]
-    fn thread_id() -> ThreadId {
-        thread_local! {
-            static ID: ThreadId = thread::current().id();
-        }
-        ID.try_with(|id| *id)
-            .unwrap_or_else(|_| thread::current().id())
-    }
-
-    struct Checked<F> {
-        id: ThreadId,
-        inner: ManuallyDrop<F>,
-    }
-
-    impl<F> Drop for Checked<F> {
-        fn drop(&mut self) {
-            assert!(
-                self.id == thread_id(),
-                "local task dropped by a thread that didn't spawn it"
-            );
-            unsafe {
-                ManuallyDrop::drop(&mut self.inner);
-            }
-        }
-    }
-
-    impl<F: Future> Future for Checked<F> {
-        type Output = F::Output;
-
-        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-            assert!(
-                self.id == thread_id(),
-                "local task polled by a thread that didn't spawn it"
-            );
-            unsafe { self.map_unchecked_mut(|c| &mut *c.inner).poll(cx) }
-        }
-    }
-
-    // Wrap the future into one that which thread it's on.
-    let future = Checked {
-        id: thread_id(),
-        inner: ManuallyDrop::new(future),
-    };
-
-    // Allocate large futures on the heap.
-    let raw_task = if mem::size_of::<F>() >= 2048 {
-        let future = alloc::boxed::Box::pin(future);
-        RawTask::<_, R, S, T>::allocate(future, schedule, tag)
-    } else {
-        RawTask::<_, R, S, T>::allocate(future, schedule, tag)
-    };
-
-    let task = Task {
-        raw_task,
-        _marker: PhantomData,
-    };
-    let handle = JoinHandle {
-        raw_task,
-        _marker: PhantomData,
-    };
-    (task, handle)
+    Builder::new().spawn_local(future, schedule, tag)
 }
 
 /// A task reference that runs its future.
@@ -304,6 +258,11 @@ impl<T> Task<T> {
     ///
     /// This method should only be used with raw pointers returned from [`into_raw`].
     ///
+    /// # Safety
+    ///
+    /// The pointer must have been returned by [`into_raw`] for a task with the same tag type, and
+    /// it must not be converted back into a task more than once.
+    ///
     /// [`into_raw`]: #method.into_raw
     pub unsafe fn from_raw(raw: *const T) -> Task<T> {
         let offset = Header::offset_tag::<T>();
@@ -315,6 +274,44 @@ impl<T> Task<T> {
         }
     }
 
+    /// Returns the name of the task, if it has one.
+    ///
+    /// Tasks are named with [`spawn_named()`] or [`Builder::name()`].
+    ///
+    /// [`spawn_named()`]: fn.spawn_named.html
+    /// [`Builder::name()`]: struct.Builder.html#method.name
+    pub fn name(&self) -> Option<&str> {
+        let ptr = self.raw_task.as_ptr();
+        let header = ptr as *const Header;
+
+        unsafe { (*header).name().map(TaskName::as_str) }
+    }
+
+    /// Returns the location in the source code where the task was spawned.
+    ///
+    /// This is the location of the call to [`spawn()`] or a similar function. Functions that
+    /// spawn tasks on behalf of their callers can be annotated with `#[track_caller]` to report
+    /// their own callers instead.
+    ///
+    /// [`spawn()`]: fn.spawn.html
+    pub fn spawn_location(&self) -> &'static Location<'static> {
+        let ptr = self.raw_task.as_ptr();
+        let header = ptr as *const Header;
+
+        unsafe { (*header).location() }
+    }
+
+    /// Returns a snapshot of the statistics of the task.
+    ///
+    /// **NOTE:** This method is only available when the `stats` feature for this crate is enabled.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::TaskStats {
+        let ptr = self.raw_task.as_ptr();
+        let header = ptr as *const Header;
+
+        unsafe { (*header).stats.snapshot() }
+    }
+
     /// Returns a waker associated with this task.
     pub fn waker(&self) -> Waker {
         let ptr = self.raw_task.as_ptr();
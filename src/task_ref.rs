@@ -0,0 +1,55 @@
+use core::ptr::NonNull;
+use core::sync::atomic::Ordering;
+use core::task::Waker;
+
+use crate::header::Header;
+use crate::state::*;
+
+/// A reference to a task that keeps it allocated.
+///
+/// The reference is counted just like a [`Waker`], but it is only ever used to inspect or cancel
+/// the task and never to wake it.
+///
+/// [`Waker`]: https://doc.rust-lang.org/std/task/struct.Waker.html
+pub(crate) struct TaskRef {
+    /// A pointer to the heap-allocated task.
+    ptr: NonNull<()>,
+
+    /// The waker holding a reference to the task.
+    _waker: Waker,
+}
+
+unsafe impl Send for TaskRef {}
+unsafe impl Sync for TaskRef {}
+
+impl TaskRef {
+    /// Creates a new reference to the task.
+    pub(crate) fn new(raw_task: NonNull<()>) -> TaskRef {
+        let ptr = raw_task.as_ptr();
+        let header = ptr as *const Header;
+
+        unsafe {
+            let raw_waker = ((*header).vtable.clone_waker)(ptr);
+            TaskRef {
+                ptr: raw_task,
+                _waker: Waker::from_raw(raw_waker),
+            }
+        }
+    }
+
+    /// Returns the header of the task.
+    pub(crate) fn header(&self) -> &Header {
+        unsafe { &*(self.ptr.as_ptr() as *const Header) }
+    }
+
+    /// Cancels the task the same way `JoinHandle::cancel()` does.
+    pub(crate) fn cancel(&self) {
+        self.header().cancel_and_schedule();
+    }
+
+    /// Returns `true` if the task has been completed or closed.
+    pub(crate) fn is_finished(&self) -> bool {
+        let state = self.header().state.load(Ordering::Acquire);
+        state & (COMPLETED | CLOSED) != 0
+    }
+}
@@ -0,0 +1,144 @@
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+
+use crate::JoinHandle;
+
+/// A source of timers.
+///
+/// This trait abstracts over the clock of an executor so that [`JoinHandle::timeout()`] and
+/// [`JoinHandle::with_deadline()`] work in `no_std` environments, in tests with virtual time, or
+/// with any other timer implementation.
+///
+/// [`JoinHandle::timeout()`]: struct.JoinHandle.html#method.timeout
+/// [`JoinHandle::with_deadline()`]: struct.JoinHandle.html#method.with_deadline
+pub trait Timer {
+    /// A point in time as understood by this timer.
+    type Instant;
+
+    /// A future that completes when a timer fires.
+    type Sleep: Future<Output = ()>;
+
+    /// Returns a future that completes after `duration` has elapsed.
+    fn sleep(&self, duration: Duration) -> Self::Sleep;
+
+    /// Returns a future that completes once `deadline` has been reached.
+    fn sleep_until(&self, deadline: Self::Instant) -> Self::Sleep;
+}
+
+/// Error returned by [`Timeout`] when its deadline has passed.
+///
+/// [`Timeout`]: struct.Timeout.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Elapsed(());
+
+impl Elapsed {
+    /// Creates a new error.
+    pub(crate) fn new() -> Elapsed {
+        Elapsed(())
+    }
+}
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "deadline has elapsed".fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Elapsed {}
+
+/// A future that awaits a [`JoinHandle`] until a deadline.
+///
+/// This future is created by [`JoinHandle::timeout()`] or [`JoinHandle::with_deadline()`]. It
+/// resolves to `Ok` with the output of the [`JoinHandle`] if the task finishes in time, or to
+/// `Err(Elapsed)` if the timer fires first.
+///
+/// By default, the task keeps running after the deadline passes. Use [`cancel_on_elapsed()`] to
+/// cancel it instead.
+///
+/// [`JoinHandle`]: struct.JoinHandle.html
+/// [`JoinHandle::timeout()`]: struct.JoinHandle.html#method.timeout
+/// [`JoinHandle::with_deadline()`]: struct.JoinHandle.html#method.with_deadline
+/// [`cancel_on_elapsed()`]: #method.cancel_on_elapsed
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Timeout<R, T, S> {
+    /// The handle being awaited.
+    handle: JoinHandle<R, T>,
+
+    /// The timer that fires at the deadline.
+    sleep: S,
+
+    /// Whether to cancel the task when the deadline passes.
+    cancel: bool,
+}
+
+impl<R, T, S> Timeout<R, T, S> {
+    /// Creates a new timeout around `handle`.
+    pub(crate) fn new(handle: JoinHandle<R, T>, sleep: S) -> Timeout<R, T, S> {
+        Timeout {
+            handle,
+            sleep,
+            cancel: false,
+        }
+    }
+
+    /// Sets whether the task gets canceled when the deadline passes.
+    ///
+    /// Cancellation has the same semantics as [`JoinHandle::cancel()`].
+    ///
+    /// [`JoinHandle::cancel()`]: struct.JoinHandle.html#method.cancel
+    pub fn cancel_on_elapsed(mut self, cancel: bool) -> Timeout<R, T, S> {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Returns a reference to the inner [`JoinHandle`].
+    ///
+    /// [`JoinHandle`]: struct.JoinHandle.html
+    pub fn handle(&self) -> &JoinHandle<R, T> {
+        &self.handle
+    }
+
+    /// Consumes the timeout and returns the inner [`JoinHandle`].
+    ///
+    /// [`JoinHandle`]: struct.JoinHandle.html
+    pub fn into_inner(self) -> JoinHandle<R, T> {
+        self.handle
+    }
+}
+
+impl<R, T, S: Future<Output = ()>> Future for Timeout<R, T, S> {
+    type Output = Result<Option<R>, Elapsed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // The handle is `Unpin`, but the timer might not be, so it is never moved out of `self`.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        // Check the task first so that an output that is ready wins over a timer that fired.
+        if let Poll::Ready(output) = Pin::new(&mut this.handle).poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+
+        let sleep = unsafe { Pin::new_unchecked(&mut this.sleep) };
+        if sleep.poll(cx).is_ready() {
+            if this.cancel {
+                this.handle.cancel();
+            }
+            return Poll::Ready(Err(Elapsed::new()));
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<R, T, S> fmt::Debug for Timeout<R, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Timeout")
+            .field("handle", &self.handle)
+            .field("cancel", &self.cancel)
+            .finish()
+    }
+}
@@ -0,0 +1,463 @@
+//! Timers driven by real or virtual time.
+//!
+//! A [`Clock`] keeps a hierarchical timer wheel and provides [`Sleep`] and [`Interval`] futures.
+//! Timers wake their tasks through the normal [`Waker`] path, so they work with any executor.
+//!
+//! A clock is driven in one of two ways:
+//!
+//! * [`Clock::real()`] follows the system clock. A background thread fires timers as they expire.
+//! * [`Clock::manual()`] only moves forward when [`Clock::advance()`] is called. Tests using it run
+//!   instantly and deterministically.
+//!
+//! Timers have a resolution of one millisecond. Deadlines are rounded up to the next millisecond.
+//!
+//! **NOTE:** This module is only available when the `timer` feature for this crate is enabled.
+//!
+//! [`Clock`]: struct.Clock.html
+//! [`Sleep`]: struct.Sleep.html
+//! [`Interval`]: struct.Interval.html
+//! [`Clock::real()`]: struct.Clock.html#method.real
+//! [`Clock::manual()`]: struct.Clock.html#method.manual
+//! [`Clock::advance()`]: struct.Clock.html#method.advance
+//! [`Waker`]: https://doc.rust-lang.org/std/task/struct.Waker.html
+//!
+//! # Examples
+//!
+//! ```
+//! use async_task::timer::Clock;
+//! use std::time::Duration;
+//!
+//! let clock = Clock::manual();
+//!
+//! let (task, handle) = async_task::spawn(
+//!     {
+//!         let clock = clock.clone();
+//!         async move { clock.sleep(Duration::from_secs(5)).await }
+//!     },
+//!     |task: async_task::Task<()>| {
+//!         task.run();
+//!     },
+//!     (),
+//! );
+//! task.run();
+//!
+//! // The timer fires and the task runs once the clock is advanced far enough.
+//! clock.advance(Duration::from_secs(5));
+//! assert_eq!(async_task::block_on(handle), Some(()));
+//! ```
+
+mod wheel;
+
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+
+use std::sync::Mutex;
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+use self::wheel::Wheel;
+
+/// A source of time with a timer wheel.
+///
+/// Instants are measured as the time elapsed since the clock was created.
+///
+/// See the [module-level documentation] for details.
+///
+/// [module-level documentation]: index.html
+#[derive(Clone)]
+pub struct Clock {
+    /// State shared with the timers and the driver thread.
+    inner: Arc<Inner>,
+}
+
+/// State shared with the timers and the driver thread.
+struct Inner {
+    /// The timer wheel, counting milliseconds.
+    wheel: Mutex<Wheel>,
+
+    /// When the clock was created, if it follows real time.
+    start: Option<Instant>,
+
+    /// The driver thread of a real clock.
+    driver: Mutex<Option<Thread>>,
+}
+
+impl Inner {
+    /// Returns the current time of a real clock in milliseconds, rounded down.
+    fn real_now(&self, start: Instant) -> u64 {
+        start.elapsed().as_millis() as u64
+    }
+
+    /// Advances the wheel to `now` and wakes the timers that fired.
+    fn advance_to(&self, now: u64) {
+        let mut fired = Vec::new();
+        self.wheel.lock().unwrap().advance(now, &mut fired);
+
+        // Wake the timers without holding the lock.
+        for waker in fired {
+            waker.wake();
+        }
+    }
+
+    /// Notifies the driver thread that a new timer was registered.
+    fn notify_driver(&self) {
+        if let Some(thread) = &*self.driver.lock().unwrap() {
+            thread.unpark();
+        }
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        // Let the driver thread notice that the clock is gone.
+        self.notify_driver();
+    }
+}
+
+/// Converts a duration into milliseconds, rounding up.
+fn to_millis(duration: Duration) -> u64 {
+    let millis = duration.as_millis() as u64;
+    if Duration::from_millis(millis) == duration {
+        millis
+    } else {
+        millis + 1
+    }
+}
+
+impl Clock {
+    /// Creates a clock that follows real time.
+    ///
+    /// Timers are fired by a background thread, which exits once all clones of the clock and all
+    /// of its timers are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the driver thread could not be spawned.
+    pub fn real() -> Clock {
+        let clock = Clock::new(Some(Instant::now()));
+
+        let weak = Arc::downgrade(&clock.inner);
+        let handle = thread::Builder::new()
+            .name(String::from("async-task-timer"))
+            .spawn(move || drive(weak))
+            .expect("cannot spawn timer thread");
+        *clock.inner.driver.lock().unwrap() = Some(handle.thread().clone());
+
+        clock
+    }
+
+    /// Creates a clock that only moves forward when advanced manually.
+    pub fn manual() -> Clock {
+        Clock::new(None)
+    }
+
+    fn new(start: Option<Instant>) -> Clock {
+        Clock {
+            inner: Arc::new(Inner {
+                wheel: Mutex::new(Wheel::new()),
+                start,
+                driver: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Returns `true` if this clock only moves forward when advanced manually.
+    pub fn is_manual(&self) -> bool {
+        self.inner.start.is_none()
+    }
+
+    /// Returns the time elapsed since the clock was created.
+    pub fn now(&self) -> Duration {
+        let millis = match self.inner.start {
+            None => self.inner.wheel.lock().unwrap().elapsed(),
+            Some(start) => return start.elapsed(),
+        };
+        Duration::from_millis(millis)
+    }
+
+    /// Moves a manual clock forward by `duration` and fires the timers that expire.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the clock follows real time.
+    pub fn advance(&self, duration: Duration) {
+        assert!(self.is_manual(), "cannot advance a real clock");
+        let now = self.inner.wheel.lock().unwrap().elapsed();
+        self.inner.advance_to(now + to_millis(duration));
+    }
+
+    /// Returns the deadline of the earliest pending timer, if there is one.
+    ///
+    /// An executor using a manual clock can advance the clock to this point when it has nothing
+    /// else to do.
+    pub fn next_deadline(&self) -> Option<Duration> {
+        let wheel = self.inner.wheel.lock().unwrap();
+        wheel.next_deadline().map(Duration::from_millis)
+    }
+
+    /// Returns a future that completes after `duration` has elapsed.
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        self.sleep_until(self.now() + duration)
+    }
+
+    /// Returns a future that completes once the clock reaches `deadline`.
+    pub fn sleep_until(&self, deadline: Duration) -> Sleep {
+        Sleep {
+            clock: self.clone(),
+            deadline: to_millis(deadline),
+            id: None,
+        }
+    }
+
+    /// Returns an interval that ticks every `period`, starting right away.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn interval(&self, period: Duration) -> Interval {
+        self.interval_at(self.now(), period)
+    }
+
+    /// Returns an interval that ticks every `period`, starting at `start`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn interval_at(&self, start: Duration, period: Duration) -> Interval {
+        assert!(
+            period > Duration::from_secs(0),
+            "interval period must be non-zero"
+        );
+        Interval {
+            sleep: self.sleep_until(start),
+            period: to_millis(period),
+        }
+    }
+
+    /// Removes all pending timers.
+    ///
+    /// Their sleeps stay pending forever.
+    #[cfg(feature = "sim")]
+    pub(crate) fn clear(&self) {
+        let mut removed = Vec::new();
+        self.inner.wheel.lock().unwrap().clear(&mut removed);
+
+        // Drop the wakers without holding the lock.
+        drop(removed);
+    }
+
+    /// Brings the wheel of a real clock up to date.
+    fn update(&self) {
+        if let Some(start) = self.inner.start {
+            self.inner.advance_to(self.inner.real_now(start));
+        }
+    }
+}
+
+impl crate::Timer for Clock {
+    type Instant = Duration;
+    type Sleep = Sleep;
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Clock::sleep(self, duration)
+    }
+
+    fn sleep_until(&self, deadline: Duration) -> Sleep {
+        Clock::sleep_until(self, deadline)
+    }
+}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Clock")
+            .field("manual", &self.is_manual())
+            .field("now", &self.now())
+            .finish()
+    }
+}
+
+/// Runs the driver thread of a real clock.
+fn drive(weak: Weak<Inner>) {
+    loop {
+        let timeout = {
+            let inner = match weak.upgrade() {
+                None => return,
+                Some(inner) => inner,
+            };
+            let start = inner.start.unwrap();
+
+            let now = inner.real_now(start);
+            inner.advance_to(now);
+
+            let next = inner.wheel.lock().unwrap().next_deadline();
+            next.map(|deadline| Duration::from_millis(deadline.saturating_sub(now)))
+        };
+
+        // Sleep until the next timer, or until a new timer is registered.
+        match timeout {
+            None => thread::park(),
+            Some(timeout) => thread::park_timeout(timeout),
+        }
+    }
+}
+
+/// A future that completes at a point in time.
+///
+/// This future is created by [`Clock::sleep()`] or [`Clock::sleep_until()`].
+///
+/// [`Clock::sleep()`]: struct.Clock.html#method.sleep
+/// [`Clock::sleep_until()`]: struct.Clock.html#method.sleep_until
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Sleep {
+    /// The clock driving the timer.
+    clock: Clock,
+
+    /// When the timer fires, in milliseconds.
+    deadline: u64,
+
+    /// The ID of the registered timer.
+    id: Option<u64>,
+}
+
+impl Sleep {
+    /// Returns the point in time at which this future completes.
+    pub fn deadline(&self) -> Duration {
+        Duration::from_millis(self.deadline)
+    }
+
+    /// Changes the deadline, registering the timer again if needed.
+    fn reset(&mut self, deadline: u64) {
+        if let Some(id) = self.id.take() {
+            self.clock.inner.wheel.lock().unwrap().remove(id);
+        }
+        self.deadline = deadline;
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.clock.update();
+
+        let mut wheel = self.clock.inner.wheel.lock().unwrap();
+
+        if wheel.elapsed() >= self.deadline {
+            if let Some(id) = self.id {
+                wheel.remove(id);
+            }
+            drop(wheel);
+            self.id = None;
+            return Poll::Ready(());
+        }
+
+        if let Some(id) = self.id {
+            if wheel.update(id, cx.waker()) {
+                return Poll::Pending;
+            }
+        }
+
+        let id = wheel.insert(self.deadline, cx.waker().clone());
+        drop(wheel);
+        self.id = Some(id);
+
+        // The driver thread might be sleeping past the new deadline.
+        self.clock.inner.notify_driver();
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.clock.inner.wheel.lock().unwrap().remove(id);
+        }
+    }
+}
+
+impl fmt::Debug for Sleep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sleep")
+            .field("deadline", &self.deadline())
+            .finish()
+    }
+}
+
+/// A stream of ticks at a fixed period.
+///
+/// This type is created by [`Clock::interval()`] or [`Clock::interval_at()`].
+///
+/// If ticks are missed because the interval wasn't polled in time, they complete right away one
+/// after another until the interval catches up.
+///
+/// [`Clock::interval()`]: struct.Clock.html#method.interval
+/// [`Clock::interval_at()`]: struct.Clock.html#method.interval_at
+pub struct Interval {
+    /// The timer for the next tick.
+    sleep: Sleep,
+
+    /// The period in milliseconds.
+    period: u64,
+}
+
+impl Interval {
+    /// Waits for the next tick and returns the time it was scheduled for.
+    pub fn tick(&mut self) -> Tick<'_> {
+        Tick { interval: self }
+    }
+
+    /// Polls for the next tick.
+    ///
+    /// Returns the time the tick was scheduled for.
+    pub fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Duration> {
+        match Pin::new(&mut self.sleep).poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(()) => {
+                let deadline = self.sleep.deadline;
+                self.sleep.reset(deadline + self.period);
+                Poll::Ready(Duration::from_millis(deadline))
+            }
+        }
+    }
+
+    /// Returns the period of the interval.
+    pub fn period(&self) -> Duration {
+        Duration::from_millis(self.period)
+    }
+}
+
+impl fmt::Debug for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interval")
+            .field("next", &self.sleep.deadline())
+            .field("period", &self.period())
+            .finish()
+    }
+}
+
+/// A future that waits for the next tick of an [`Interval`].
+///
+/// This future is created by [`Interval::tick()`].
+///
+/// [`Interval`]: struct.Interval.html
+/// [`Interval::tick()`]: struct.Interval.html#method.tick
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct Tick<'a> {
+    /// The interval being waited on.
+    interval: &'a mut Interval,
+}
+
+impl Future for Tick<'_> {
+    type Output = Duration;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Duration> {
+        self.interval.poll_tick(cx)
+    }
+}
@@ -0,0 +1,248 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::task::Waker;
+
+/// Number of bits of a deadline covered by each level.
+const BITS: u32 = 6;
+
+/// Number of slots in each level.
+const SLOTS: usize = 1 << BITS;
+
+/// Number of levels.
+const LEVELS: usize = 6;
+
+/// The furthest a timer can be placed into the wheel, in ticks.
+///
+/// Timers further away are placed at this distance and moved closer when their slot is reached.
+/// It stays one slot short of a full turn of the top level, so that such timers always land in a
+/// future slot rather than in the current one.
+const MAX_DISTANCE: u64 = (SLOTS as u64 - 1) << (BITS * (LEVELS as u32 - 1));
+
+/// A hierarchical timer wheel.
+///
+/// Time is measured in ticks. Level `n` has 64 slots that each cover `64^n` ticks, so level 0 holds
+/// timers firing within the next 64 ticks, level 1 within the next 4096 ticks, and so on. When the
+/// wheel reaches a slot on a higher level, its timers are moved down to lower levels until they
+/// end up in level 0, where they fire.
+pub(crate) struct Wheel {
+    /// The current time.
+    elapsed: u64,
+
+    /// Timer IDs in each slot of each level.
+    levels: Vec<Level>,
+
+    /// Registered timers by ID.
+    entries: BTreeMap<u64, Entry>,
+
+    /// The ID of the next registered timer.
+    next_id: u64,
+}
+
+/// A level of the wheel.
+struct Level {
+    /// Bitmask of non-empty slots.
+    occupied: u64,
+
+    /// Timer IDs in each slot.
+    slots: Vec<Vec<u64>>,
+}
+
+/// A registered timer.
+struct Entry {
+    /// When the timer fires.
+    deadline: u64,
+
+    /// Woken when the timer fires.
+    waker: Waker,
+
+    /// The level and slot the timer is in.
+    position: (usize, usize),
+}
+
+impl Wheel {
+    /// Creates an empty wheel at time zero.
+    pub(crate) fn new() -> Wheel {
+        Wheel {
+            elapsed: 0,
+            levels: (0..LEVELS)
+                .map(|_| Level {
+                    occupied: 0,
+                    slots: (0..SLOTS).map(|_| Vec::new()).collect(),
+                })
+                .collect(),
+            entries: BTreeMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Returns the current time.
+    pub(crate) fn elapsed(&self) -> u64 {
+        self.elapsed
+    }
+
+    /// Registers a timer that wakes `waker` at `deadline`, and returns its ID.
+    ///
+    /// The deadline must be in the future.
+    pub(crate) fn insert(&mut self, deadline: u64, waker: Waker) -> u64 {
+        debug_assert!(deadline > self.elapsed);
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.insert(
+            id,
+            Entry {
+                deadline,
+                waker,
+                position: (0, 0),
+            },
+        );
+        self.place(id, deadline);
+        id
+    }
+
+    /// Replaces the waker of a registered timer.
+    ///
+    /// Returns `false` if there is no such timer.
+    pub(crate) fn update(&mut self, id: u64, waker: &Waker) -> bool {
+        match self.entries.get_mut(&id) {
+            None => false,
+            Some(entry) => {
+                if !entry.waker.will_wake(waker) {
+                    entry.waker = waker.clone();
+                }
+                true
+            }
+        }
+    }
+
+    /// Removes a registered timer.
+    pub(crate) fn remove(&mut self, id: u64) {
+        let (level, slot) = match self.entries.remove(&id) {
+            None => return,
+            Some(entry) => entry.position,
+        };
+
+        let level = &mut self.levels[level];
+        let ids = &mut level.slots[slot];
+        if let Some(index) = ids.iter().position(|&i| i == id) {
+            ids.remove(index);
+        }
+        if ids.is_empty() {
+            level.occupied &= !(1 << slot);
+        }
+    }
+
+    /// Returns the earliest deadline of all registered timers.
+    ///
+    /// Only the next slot to be processed is inspected. If all of its timers are further away than
+    /// the wheel reaches, the start of the slot is returned instead, where they get moved closer.
+    pub(crate) fn next_deadline(&self) -> Option<u64> {
+        let (level, slot, when) = self.next_expiration()?;
+        let earliest = self.levels[level].slots[slot]
+            .iter()
+            .map(|id| self.entries[id].deadline)
+            .min()?;
+
+        let slot_range = 1u64 << (level as u32 * BITS);
+        let slot_end = (when & !(slot_range - 1)) + slot_range;
+        if earliest < slot_end {
+            Some(earliest)
+        } else {
+            Some(when)
+        }
+    }
+
+    /// Advances the wheel to time `now` and returns the wakers of timers that fired.
+    ///
+    /// The wakers are returned rather than woken so that they can be woken without holding a lock
+    /// on the wheel.
+    pub(crate) fn advance(&mut self, now: u64, fired: &mut Vec<Waker>) {
+        while let Some((level, slot, when)) = self.next_expiration() {
+            if when > now {
+                break;
+            }
+
+            self.elapsed = self.elapsed.max(when);
+            self.levels[level].occupied &= !(1 << slot);
+            let ids = core::mem::take(&mut self.levels[level].slots[slot]);
+
+            for id in ids {
+                let deadline = self.entries[&id].deadline;
+
+                if deadline <= self.elapsed {
+                    fired.push(self.entries.remove(&id).unwrap().waker);
+                } else {
+                    // Move the timer down to a lower level.
+                    self.place(id, deadline);
+                }
+            }
+        }
+
+        self.elapsed = self.elapsed.max(now);
+    }
+
+    /// Removes all registered timers and returns their wakers.
+    ///
+    /// Like in [`advance()`], the wakers are returned so that they can be dropped without holding
+    /// a lock on the wheel.
+    ///
+    /// [`advance()`]: #method.advance
+    #[cfg(feature = "sim")]
+    pub(crate) fn clear(&mut self, removed: &mut Vec<Waker>) {
+        for level in &mut self.levels {
+            level.occupied = 0;
+            for ids in &mut level.slots {
+                ids.clear();
+            }
+        }
+        let entries = core::mem::take(&mut self.entries);
+        removed.extend(entries.into_values().map(|entry| entry.waker));
+    }
+
+    /// Puts a timer into the slot its deadline belongs to.
+    fn place(&mut self, id: u64, deadline: u64) {
+        let when = deadline.min(self.elapsed + MAX_DISTANCE);
+
+        // The level is determined by the most significant bit in which the deadline differs from
+        // the current time.
+        let masked = (self.elapsed ^ when) | (SLOTS as u64 - 1);
+        let significant = 63 - masked.leading_zeros();
+        let level = ((significant / BITS) as usize).min(LEVELS - 1);
+        let slot = ((when >> (level as u32 * BITS)) as usize) & (SLOTS - 1);
+
+        self.levels[level].slots[slot].push(id);
+        self.levels[level].occupied |= 1 << slot;
+        if let Some(entry) = self.entries.get_mut(&id) {
+            entry.position = (level, slot);
+        }
+    }
+
+    /// Returns the level, slot, and start time of the next slot to be processed.
+    fn next_expiration(&self) -> Option<(usize, usize, u64)> {
+        for (index, level) in self.levels.iter().enumerate() {
+            if level.occupied == 0 {
+                continue;
+            }
+
+            let shift = index as u32 * BITS;
+            let slot_range = 1u64 << shift;
+            let level_range = slot_range << BITS;
+            let now_slot = ((self.elapsed >> shift) as usize) & (SLOTS - 1);
+
+            // Find the first occupied slot at or after the current one.
+            let rotated = level.occupied.rotate_right(now_slot as u32);
+            let slot = (now_slot + rotated.trailing_zeros() as usize) % SLOTS;
+
+            let level_start = self.elapsed & !(level_range - 1);
+            let mut when = level_start + slot as u64 * slot_range;
+            if slot < now_slot {
+                when += level_range;
+            }
+
+            // Lower levels always fire before higher levels, so the first non-empty level wins.
+            return Some((index, slot, when.max(self.elapsed)));
+        }
+
+        None
+    }
+}
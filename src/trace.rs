@@ -0,0 +1,46 @@
+use tracing::Span;
+
+use crate::header::Header;
+
+/// Creates the span of a newly spawned task.
+///
+/// Tasks outlive the code that spawns them, so the span is a root that only follows from the
+/// current span instead of being its child.
+pub(crate) fn span(id: u64, name: Option<&str>) -> Span {
+    let span = tracing::trace_span!(
+        target: "async_task",
+        parent: None,
+        "task",
+        task.id = id,
+        task.name = name
+    );
+    span.follows_from(Span::current());
+    span
+}
+
+/// Records that a task was woken.
+pub(crate) fn wake(header: &Header) {
+    tracing::trace!(target: "async_task", parent: &header.span, { task.id = header.id }, "task woken");
+}
+
+/// Records that a task was canceled.
+pub(crate) fn cancel(header: &Header) {
+    tracing::trace!(target: "async_task", parent: &header.span, { task.id = header.id }, "task canceled");
+}
+
+/// Records that the future of a task completed.
+pub(crate) fn complete(header: &Header) {
+    tracing::trace!(target: "async_task", parent: &header.span, { task.id = header.id }, "task completed");
+}
+
+/// Records that a task was woken redundantly `count` times.
+#[cfg(feature = "stats")]
+pub(crate) fn wake_storm(header: &Header, count: u64) {
+    tracing::warn!(
+        target: "async_task",
+        parent: &header.span,
+        { task.id = header.id, task.redundant_wakes = count },
+        "task woken {} times while already scheduled",
+        count
+    );
+}
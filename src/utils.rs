@@ -36,6 +36,73 @@ pub(crate) fn abort_on_panic<T>(f: impl FnOnce() -> T) -> T {
     t
 }
 
+/// Returns the ID of the current thread.
+///
+/// The ID is cached in a thread-local because `thread::current()` is comparatively slow.
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn thread_id() -> std::thread::ThreadId {
+    use std::thread;
+    use std::thread_local;
+
+    thread_local! {
+        static ID: thread::ThreadId = thread::current().id();
+    }
+    ID.try_with(|id| *id)
+        .unwrap_or_else(|_| thread::current().id())
+}
+
+/// The ID of the next allocated task.
+#[cfg(all(
+    target_has_atomic = "64",
+    any(
+        feature = "hooks",
+        feature = "registry",
+        feature = "tracing",
+        feature = "watchdog"
+    )
+))]
+static NEXT_TASK_ID: core::sync::atomic::AtomicU64 = core::sync::atomic::AtomicU64::new(1);
+
+/// The ID of the next allocated task, on targets without 64-bit atomics.
+#[cfg(all(
+    not(target_has_atomic = "64"),
+    any(
+        feature = "hooks",
+        feature = "registry",
+        feature = "tracing",
+        feature = "watchdog"
+    )
+))]
+static NEXT_TASK_ID: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(1);
+
+/// Returns a new task ID.
+///
+/// IDs are assigned in order of allocation and never reused, except on targets without 64-bit
+/// atomics, where they wrap around after `usize::MAX` tasks.
+#[cfg(any(
+    feature = "hooks",
+    feature = "registry",
+    feature = "tracing",
+    feature = "watchdog"
+))]
+#[inline]
+pub(crate) fn next_task_id() -> u64 {
+    let id = NEXT_TASK_ID.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+
+    // Widen the ID on targets without 64-bit atomics.
+    #[cfg(not(target_has_atomic = "64"))]
+    let id = id as u64;
+
+    id
+}
+
+/// Returns the ID the next allocated task will get.
+#[cfg(feature = "registry")]
+pub(crate) fn peek_task_id() -> u64 {
+    NEXT_TASK_ID.load(core::sync::atomic::Ordering::Relaxed)
+}
+
 /// Returns the layout for `a` followed by `b` and the offset of `b`.
 ///
 /// This function was adapted from the currently unstable `Layout::extend()`:
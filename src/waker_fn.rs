@@ -23,7 +23,7 @@ impl<F: Fn() + Send + Sync + 'static> Helper<F> {
 
     unsafe fn clone_waker(ptr: *const ()) -> RawWaker {
         let arc = ManuallyDrop::new(Arc::from_raw(ptr as *const F));
-        mem::forget(arc.clone());
+        mem::forget(Arc::clone(&arc));
         RawWaker::new(ptr, &Self::VTABLE)
     }
 
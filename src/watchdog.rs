@@ -0,0 +1,432 @@
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use core::time::Duration;
+
+use std::backtrace::Backtrace;
+use std::sync::Mutex;
+use std::thread::{self, Thread, ThreadId};
+use std::thread_local;
+use std::time::Instant;
+
+use crate::header::Header;
+
+/// The polling slots of all threads that have run tasks.
+static SLOTS: Mutex<Vec<Weak<Slot>>> = Mutex::new(Vec::new());
+
+/// Follow-up reports of slow polls that have finished, waiting to be handed to watchdogs.
+///
+/// They are kept outside of the slots because the polling thread may exit before a watchdog
+/// collects them.
+static FOLLOW_UPS: Mutex<Vec<FollowUp>> = Mutex::new(Vec::new());
+
+/// Number of running watchdogs.
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The polling slot of this thread.
+    static SLOT: Arc<Slot> = {
+        let slot = Arc::new(Slot {
+            thread: thread::current(),
+            polling: Mutex::new(None),
+        });
+        SLOTS.lock().unwrap_or_else(|e| e.into_inner()).push(Arc::downgrade(&slot));
+        slot
+    };
+}
+
+/// Records which task a thread is polling, and since when.
+struct Slot {
+    /// The thread owning the slot.
+    thread: Thread,
+
+    /// The poll in progress on the thread.
+    ///
+    /// The poll is cleared right after the future returns, before the task can be destroyed.
+    /// Clearing it waits for the lock, so the task stays alive while the watchdog inspects it.
+    polling: Mutex<Option<Polling>>,
+}
+
+/// A poll in progress.
+struct Polling {
+    /// The task being polled.
+    header: *const Header,
+
+    /// When polling started.
+    since: Instant,
+
+    /// The report of the poll, once it has been reported as slow.
+    report: Option<SlowPoll>,
+
+    /// The watchdogs that have reported the poll.
+    watchdogs: Vec<Weak<Shared>>,
+}
+
+/// A follow-up report of a finished slow poll.
+struct FollowUp {
+    /// The report, including a backtrace.
+    report: SlowPoll,
+
+    /// The watchdogs that reported the poll and haven't received the follow-up yet.
+    watchdogs: Vec<Weak<Shared>>,
+}
+
+// The header is only accessed while the poll is recorded in the slot, which keeps the task alive.
+unsafe impl Send for Polling {}
+
+/// Restores the previous poll of this thread when dropped.
+pub(crate) struct Enter(Option<Polling>);
+
+impl Drop for Enter {
+    fn drop(&mut self) {
+        let prev = self.0.take();
+        let _ = SLOT.try_with(|slot| {
+            let done = mem::replace(
+                &mut *slot.polling.lock().unwrap_or_else(|e| e.into_inner()),
+                prev,
+            );
+
+            // If the poll was reported as slow, follow up with a backtrace of this thread.
+            if let Some(Polling {
+                since,
+                report: Some(report),
+                mut watchdogs,
+                ..
+            }) = done
+            {
+                watchdogs.retain(|w| w.strong_count() > 0);
+                if !watchdogs.is_empty() {
+                    let report = SlowPoll {
+                        elapsed: since.elapsed(),
+                        backtrace: Some(Arc::new(Backtrace::force_capture())),
+                        ..report
+                    };
+                    FOLLOW_UPS
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(FollowUp { report, watchdogs });
+                }
+            }
+        });
+    }
+}
+
+/// Records that this thread is about to poll the task with the given header.
+///
+/// Nothing is recorded unless a watchdog is running.
+#[inline]
+pub(crate) fn enter(header: *const Header) -> Option<Enter> {
+    if ACTIVE.load(Ordering::Relaxed) == 0 {
+        return None;
+    }
+
+    let polling = Polling {
+        header,
+        since: Instant::now(),
+        report: None,
+        watchdogs: Vec::new(),
+    };
+    SLOT.try_with(|slot| {
+        let prev = slot
+            .polling
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace(polling);
+        Enter(prev)
+    })
+    .ok()
+}
+
+/// A detector of tasks that block the thread polling them.
+///
+/// A watchdog runs a background thread named `async-task-watchdog`, which periodically checks
+/// every thread that is running a task. Whenever polling a future takes longer than the threshold,
+/// the handler is invoked with a [`SlowPoll`] report while the poll is still in progress, so that
+/// futures that never return are caught as well.
+///
+/// Blocking calls inside async code, like synchronous I/O or locking a contended mutex, stall
+/// every other task on the same thread, and this helps find them.
+///
+/// Reports include the ID and tag of the task and the thread polling it. The standard library can
+/// only capture the backtrace of the current thread, so the first report of a slow poll has no
+/// backtrace. Once the slow poll finishes, the polling thread captures its backtrace and the
+/// handler is invoked again with a follow-up report, for which [`SlowPoll::is_finished()`]
+/// returns `true`. The backtrace shows the call path that polled the task, like the executor and
+/// worker running it, but not the blocking call inside the future, which has already returned.
+/// To see where a thread is stuck, attach a debugger or a profiler to it while the poll is still
+/// in progress.
+///
+/// Polls are only tracked while at least one watchdog is running, and the watchdog stops when
+/// dropped.
+///
+/// **NOTE:** This type is only available when the `watchdog` feature for this crate is enabled.
+///
+/// [`SlowPoll`]: struct.SlowPoll.html
+/// [`SlowPoll::is_finished()`]: struct.SlowPoll.html#method.is_finished
+///
+/// # Examples
+///
+/// ```
+/// use async_task::Watchdog;
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// let watchdog = Watchdog::new(Duration::from_millis(50), |report| {
+///     eprintln!("{}", report);
+/// });
+///
+/// let future = async {
+///     // Oops, this blocks the thread.
+///     thread::sleep(Duration::from_millis(200));
+/// };
+/// let (task, handle) = async_task::spawn(future, |_| {}, ());
+/// task.run();
+/// ```
+pub struct Watchdog {
+    /// State shared with the watchdog thread.
+    shared: Arc<Shared>,
+
+    /// The watchdog thread.
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+/// State shared with the watchdog thread.
+struct Shared {
+    /// Polls taking longer than this are reported.
+    threshold: Duration,
+
+    /// Set when the watchdog is dropped.
+    stop: AtomicBool,
+}
+
+impl Watchdog {
+    /// Starts a watchdog that invokes `handler` for every poll taking longer than `threshold`.
+    ///
+    /// Slow polls are detected within a quarter of the threshold after it is exceeded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the watchdog thread could not be spawned.
+    pub fn new<H>(threshold: Duration, handler: H) -> Watchdog
+    where
+        H: Fn(SlowPoll) + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            threshold,
+            stop: AtomicBool::new(false),
+        });
+        ACTIVE.fetch_add(1, Ordering::SeqCst);
+
+        let thread = thread::Builder::new()
+            .name("async-task-watchdog".into())
+            .spawn({
+                let shared = shared.clone();
+                move || shared.run(handler)
+            })
+            .expect("cannot spawn watchdog thread");
+
+        Watchdog {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    /// Returns the threshold above which polls are reported.
+    pub fn threshold(&self) -> Duration {
+        self.shared.threshold
+    }
+}
+
+impl Shared {
+    /// Runs the main loop of the watchdog thread.
+    fn run(self: &Arc<Self>, handler: impl Fn(SlowPoll)) {
+        let interval = (self.threshold / 4).max(Duration::from_millis(1));
+
+        while !self.stop.load(Ordering::SeqCst) {
+            for report in self.check() {
+                handler(report);
+            }
+            thread::park_timeout(interval);
+        }
+    }
+
+    /// Returns the polls that have just exceeded the threshold, and follow-ups of finished ones.
+    ///
+    /// Every watchdog reports each slow poll that exceeds its own threshold once.
+    fn check(self: &Arc<Self>) -> Vec<SlowPoll> {
+        let is_self = |w: &Weak<Shared>| ptr::eq(w.as_ptr(), Arc::as_ptr(self));
+
+        let slots: Vec<Arc<Slot>> = {
+            let mut slots = SLOTS.lock().unwrap_or_else(|e| e.into_inner());
+            slots.retain(|s| s.strong_count() > 0);
+            slots.iter().filter_map(|s| s.upgrade()).collect()
+        };
+
+        let mut reports = Vec::new();
+        FOLLOW_UPS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain_mut(|f| {
+                if let Some(i) = f.watchdogs.iter().position(is_self) {
+                    f.watchdogs.swap_remove(i);
+                    reports.push(f.report.clone());
+                }
+                f.watchdogs.retain(|w| w.strong_count() > 0);
+                !f.watchdogs.is_empty()
+            });
+
+        for slot in slots {
+            let mut polling = slot.polling.lock().unwrap_or_else(|e| e.into_inner());
+
+            if let Some(p) = polling.as_mut() {
+                let elapsed = p.since.elapsed();
+
+                if elapsed >= self.threshold && !p.watchdogs.iter().any(is_self) {
+                    let header = p.header;
+                    let report = p.report.get_or_insert_with(|| {
+                        // The task is alive because the poll can't be cleared while the lock is
+                        // held.
+                        SlowPoll {
+                            id: unsafe { (*header).id },
+                            tag: unsafe { crate::debug_tag::format(header) },
+                            thread: slot.thread.id(),
+                            thread_name: slot.thread.name().map(String::from),
+                            elapsed,
+                            backtrace: None,
+                        }
+                    });
+                    p.watchdogs.push(Arc::downgrade(self));
+                    reports.push(SlowPoll {
+                        elapsed,
+                        ..report.clone()
+                    });
+                }
+            }
+        }
+        reports
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::SeqCst);
+        ACTIVE.fetch_sub(1, Ordering::SeqCst);
+
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl fmt::Debug for Watchdog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watchdog")
+            .field("threshold", &self.shared.threshold)
+            .finish()
+    }
+}
+
+/// A report of a poll that took longer than the threshold of a [`Watchdog`].
+///
+/// **NOTE:** This type is only available when the `watchdog` feature for this crate is enabled.
+///
+/// [`Watchdog`]: struct.Watchdog.html
+#[derive(Clone, Debug)]
+pub struct SlowPoll {
+    /// The ID of the task.
+    id: u64,
+
+    /// The formatted tag of the task.
+    tag: String,
+
+    /// The thread polling the task.
+    thread: ThreadId,
+
+    /// The name of the thread polling the task.
+    thread_name: Option<String>,
+
+    /// How long the poll had been running when it was detected, or its duration if finished.
+    elapsed: Duration,
+
+    /// The backtrace of the polling thread, captured when the poll finished.
+    backtrace: Option<Arc<Backtrace>>,
+}
+
+impl SlowPoll {
+    /// Returns the ID of the task.
+    ///
+    /// IDs are assigned in order of allocation and never reused.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the formatted tag of the task.
+    ///
+    /// This is the [`Debug`] output of the tag if its type has been registered with
+    /// [`debug_tags()`], and the name of its type in angle brackets otherwise.
+    ///
+    /// [`Debug`]: https://doc.rust-lang.org/std/fmt/trait.Debug.html
+    /// [`debug_tags()`]: fn.debug_tags.html
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Returns the ID of the thread polling the task.
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread
+    }
+
+    /// Returns the name of the thread polling the task, if it has one.
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread_name.as_deref()
+    }
+
+    /// Returns how long the poll had been running when it was detected.
+    ///
+    /// For a follow-up report, this is the duration of the whole poll. Otherwise, the poll may
+    /// still be running, so this is a lower bound on its duration.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns `true` if this is the follow-up report sent after the slow poll finished.
+    pub fn is_finished(&self) -> bool {
+        self.backtrace.is_some()
+    }
+
+    /// Returns the backtrace of the polling thread, captured when the slow poll finished.
+    ///
+    /// Only follow-up reports have a backtrace. It is captured even if backtraces are disabled
+    /// through the `RUST_BACKTRACE` environment variable, but may still be unsupported on the
+    /// platform.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_deref()
+    }
+}
+
+/// Formats the report as a single line, for example:
+///
+/// ```text
+/// task 3 (tag 7) blocked thread "worker" for 1.2s
+/// ```
+///
+/// Follow-up reports end with `(finished)`. The backtrace is not included.
+impl fmt::Display for SlowPoll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task {} (tag {}) blocked thread ", self.id, self.tag)?;
+        match &self.thread_name {
+            Some(name) => write!(f, "{:?}", name)?,
+            None => write!(f, "{:?}", self.thread)?,
+        }
+        write!(f, " for {:?}", self.elapsed)?;
+        if self.is_finished() {
+            f.write_str(" (finished)")?;
+        }
+        Ok(())
+    }
+}
@@ -24,6 +24,7 @@ macro_rules! future {
         }
 
         let $name = {
+            #[allow(dead_code)]
             struct Fut(Box<i32>);
 
             impl Future for Fut {
@@ -61,6 +62,7 @@ macro_rules! schedule {
         }
 
         let $name = {
+            #[allow(dead_code)]
             struct Guard(Box<i32>);
 
             impl Drop for Guard {
@@ -71,7 +73,7 @@ macro_rules! schedule {
 
             let guard = Guard(Box::new(0));
             move |_task| {
-                &guard;
+                let _ = &guard;
                 $sched.fetch_add(1);
             }
         };
@@ -92,6 +94,7 @@ macro_rules! task {
         }
 
         let ($task, $handle) = {
+            #[allow(dead_code)]
             struct Tag(Box<i32>);
 
             impl Drop for Tag {
@@ -406,3 +409,17 @@ fn raw() {
     assert_eq!(task.tag().load(Ordering::SeqCst), 8);
     task.run();
 }
+
+#[test]
+fn spawn_location() {
+    let line = line!() + 1;
+    let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+
+    assert_eq!(task.spawn_location().file(), file!());
+    assert_eq!(task.spawn_location().line(), line);
+    assert_eq!(handle.spawn_location(), task.spawn_location());
+
+    let location = format!("location: {}:{}:", file!(), line);
+    assert!(format!("{:?}", task).contains(&location));
+    assert!(format!("{:?}", handle).contains(&location));
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "std")]
+
+use std::thread;
+use std::time::Duration;
+
+use async_task::{block_on, block_on_timeout};
+use futures::channel::oneshot;
+use futures::future;
+
+#[test]
+fn ready() {
+    assert_eq!(block_on(async { 1 + 2 }), 3);
+}
+
+#[test]
+fn wake_from_other_thread() {
+    let (s, r) = oneshot::channel();
+
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        s.send("hi").unwrap();
+    });
+    assert_eq!(block_on(r), Ok("hi"));
+}
+
+#[test]
+fn reentrant() {
+    let out = block_on(async {
+        let (s, r) = oneshot::channel();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            s.send(5).unwrap();
+        });
+        block_on(r).unwrap() + block_on(async { 1 })
+    });
+    assert_eq!(out, 6);
+}
+
+#[test]
+fn spawned_task() {
+    let (task, handle) = async_task::spawn(async { 7 }, drop, ());
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        task.run();
+    });
+    assert_eq!(block_on(handle), Some(7));
+}
+
+#[test]
+fn timeout() {
+    let res = block_on_timeout(future::pending::<()>(), Duration::from_millis(50));
+    assert!(res.is_err());
+
+    let res = block_on_timeout(async { 1 }, Duration::from_millis(50));
+    assert_eq!(res, Ok(1));
+
+    let (s, r) = oneshot::channel();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        s.send(2).unwrap();
+    });
+    assert_eq!(block_on_timeout(r, Duration::from_secs(10)), Ok(Ok(2)));
+}
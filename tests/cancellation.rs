@@ -0,0 +1,138 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_task::{CancellationToken, Task};
+use crossbeam::channel::{unbounded, Receiver};
+use futures::executor::block_on;
+use futures::future;
+
+// Increments a counter when dropped.
+struct Guard(Arc<AtomicUsize>);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+// Runs all tasks in the queue until it is empty.
+fn run_all(queue: &Receiver<Task<()>>) {
+    while let Ok(task) = queue.try_recv() {
+        task.run();
+    }
+}
+
+#[test]
+fn cancel_attached() {
+    let token = CancellationToken::new();
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (s, r) = unbounded();
+    let mut handles = Vec::new();
+
+    for _ in 0..3 {
+        let guard = Guard(drops.clone());
+        let s = s.clone();
+        let (task, handle) = async_task::spawn(
+            async move {
+                let _guard = guard;
+                future::pending::<()>().await
+            },
+            move |t| s.send(t).unwrap(),
+            (),
+        );
+        token.attach(&handle);
+        task.schedule();
+        handles.push(handle);
+    }
+    run_all(&r);
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+    token.clone().cancel();
+    run_all(&r);
+    assert_eq!(drops.load(Ordering::SeqCst), 3);
+
+    for handle in handles {
+        assert_eq!(block_on(handle), None);
+    }
+}
+
+#[test]
+fn spawn_attached() {
+    let token = CancellationToken::new();
+    let drops = Arc::new(AtomicUsize::new(0));
+    let guard = Guard(drops.clone());
+
+    let (task, handle) = token.spawn(
+        async move {
+            let _guard = guard;
+            future::pending::<()>().await
+        },
+        |_| {},
+        (),
+    );
+    task.run();
+
+    token.cancel();
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+    assert_eq!(block_on(handle), None);
+}
+
+#[test]
+fn spawn_after_cancel() {
+    let token = CancellationToken::new();
+    token.cancel();
+
+    let (task, handle) = token.spawn(future::pending::<()>(), |_| {}, ());
+    task.run();
+    assert_eq!(block_on(handle), None);
+}
+
+#[test]
+fn completed_tasks_are_forgotten() {
+    let token = CancellationToken::new();
+    let drops = Arc::new(AtomicUsize::new(0));
+
+    // The tag is dropped when the task is deallocated.
+    let (task, handle) = token.spawn(async { 1 }, |_| {}, Guard(drops.clone()));
+    task.run();
+    assert_eq!(block_on(handle), Some(1));
+
+    // The token doesn't keep the completed task allocated.
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn attach_after_cancel() {
+    let token = CancellationToken::new();
+    token.cancel();
+
+    let (task, handle) = async_task::spawn(future::pending::<()>(), |_| {}, ());
+    token.attach(&handle);
+
+    task.run();
+    assert_eq!(block_on(handle), None);
+}
+
+#[test]
+fn cooperative() {
+    let token = CancellationToken::new();
+    let (s, r) = unbounded();
+
+    let (task, handle) = async_task::spawn(
+        {
+            let token = token.clone();
+            async move {
+                token.cancelled().await;
+                "cleaned up"
+            }
+        },
+        move |t| s.send(t).unwrap(),
+        (),
+    );
+    task.schedule();
+    run_all(&r);
+
+    token.cancel();
+    run_all(&r);
+    assert_eq!(block_on(handle), Some("cleaned up"));
+}
@@ -0,0 +1,248 @@
+#![cfg(feature = "hooks")]
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::Once;
+use std::task::{Context, Poll};
+
+use async_task::{PollOutcome, TaskHooks, TaskId};
+use futures::channel::oneshot;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Event {
+    Spawn,
+    Schedule,
+    PollStart,
+    PollEnd(PollOutcome),
+    Wake(Option<TaskId>),
+    Cancel,
+    Complete,
+    Destroy,
+}
+
+thread_local! {
+    // Hooks are global, so events are recorded per thread to keep concurrent tests apart.
+    static EVENTS: RefCell<Vec<(TaskId, Event)>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Recorder;
+
+impl Recorder {
+    fn push(&self, task: TaskId, event: Event) {
+        EVENTS.with(|e| e.borrow_mut().push((task, event)));
+    }
+}
+
+impl TaskHooks for Recorder {
+    fn on_spawn(&self, task: TaskId) {
+        self.push(task, Event::Spawn);
+    }
+
+    fn on_schedule(&self, task: TaskId) {
+        self.push(task, Event::Schedule);
+    }
+
+    fn on_poll_start(&self, task: TaskId) {
+        self.push(task, Event::PollStart);
+    }
+
+    fn on_poll_end(&self, task: TaskId, outcome: PollOutcome) {
+        self.push(task, Event::PollEnd(outcome));
+    }
+
+    fn on_wake(&self, task: TaskId, from: Option<TaskId>) {
+        self.push(task, Event::Wake(from));
+    }
+
+    fn on_cancel(&self, task: TaskId) {
+        self.push(task, Event::Cancel);
+    }
+
+    fn on_complete(&self, task: TaskId) {
+        self.push(task, Event::Complete);
+    }
+
+    fn on_destroy(&self, task: TaskId) {
+        self.push(task, Event::Destroy);
+    }
+}
+
+/// Installs the recorder and clears the events of this thread.
+fn setup() {
+    static INIT: Once = Once::new();
+    static RECORDER: Recorder = Recorder;
+
+    INIT.call_once(|| async_task::set_hooks(&RECORDER).unwrap());
+    EVENTS.with(|e| e.borrow_mut().clear());
+}
+
+/// Returns the events recorded on this thread.
+fn events() -> Vec<(TaskId, Event)> {
+    EVENTS.with(|e| e.borrow().clone())
+}
+
+/// Returns the events recorded on this thread, without task IDs.
+fn kinds() -> Vec<Event> {
+    events().into_iter().map(|(_, e)| e).collect()
+}
+
+#[test]
+fn set_twice() {
+    setup();
+    static OTHER: Recorder = Recorder;
+    assert!(async_task::set_hooks(&OTHER).is_err());
+}
+
+#[test]
+fn lifecycle() {
+    setup();
+
+    let (task, handle) = async_task::spawn(async { 1 }, |_| {}, ());
+    task.run();
+    assert_eq!(futures::executor::block_on(handle), Some(1));
+
+    assert_eq!(
+        kinds(),
+        [
+            Event::Spawn,
+            Event::PollStart,
+            Event::PollEnd(PollOutcome::Ready),
+            Event::Complete,
+            Event::Destroy,
+        ]
+    );
+
+    // All events belong to the same task.
+    let id = events()[0].0;
+    assert!(events().iter().all(|&(t, _)| t == id));
+}
+
+#[test]
+fn ids_not_reused() {
+    setup();
+
+    // The second task is likely allocated where the first one was.
+    for _ in 0..2 {
+        let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+        drop(task);
+        drop(handle);
+    }
+
+    let spawns: Vec<_> = events()
+        .into_iter()
+        .filter(|&(_, e)| e == Event::Spawn)
+        .map(|(t, _)| t)
+        .collect();
+    assert_eq!(spawns.len(), 2);
+    assert!(spawns[0] < spawns[1]);
+}
+
+#[test]
+fn wake_and_schedule() {
+    setup();
+
+    let (s, r) = oneshot::channel::<()>();
+    let (queue_s, queue_r) = crossbeam::channel::unbounded();
+    let (task, handle) = async_task::spawn(
+        async {
+            let _ = r.await;
+        },
+        move |t| queue_s.send(t).unwrap(),
+        (),
+    );
+    task.schedule();
+    queue_r.recv().unwrap().run();
+    s.send(()).unwrap();
+    queue_r.recv().unwrap().run();
+    drop(handle);
+
+    assert_eq!(
+        kinds(),
+        [
+            Event::Spawn,
+            Event::Schedule,
+            Event::PollStart,
+            Event::PollEnd(PollOutcome::Pending),
+            Event::Wake(None),
+            Event::Schedule,
+            Event::PollStart,
+            Event::PollEnd(PollOutcome::Ready),
+            Event::Complete,
+            Event::Destroy,
+        ]
+    );
+}
+
+#[test]
+fn wake_from_task() {
+    setup();
+
+    let (s, r) = oneshot::channel::<()>();
+    let (waiter, waiter_handle) = async_task::spawn(
+        async {
+            let _ = r.await;
+        },
+        |_| {},
+        (),
+    );
+    let (waker, waker_handle) = async_task::spawn(
+        async {
+            s.send(()).unwrap();
+        },
+        |_| {},
+        (),
+    );
+    waiter.run();
+    waker.run();
+
+    let events = events();
+    let waiter_id = events[0].0;
+    let waker_id = events[1].0;
+    assert!(events.contains(&(waiter_id, Event::Wake(Some(waker_id)))));
+
+    drop(waiter_handle);
+    drop(waker_handle);
+}
+
+#[test]
+fn cancel() {
+    setup();
+
+    let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+    handle.cancel();
+    drop(task);
+    drop(handle);
+
+    assert_eq!(kinds(), [Event::Spawn, Event::Cancel, Event::Destroy]);
+}
+
+#[test]
+fn panic() {
+    setup();
+
+    struct Panic;
+
+    impl Future for Panic {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+            panic!("boom");
+        }
+    }
+
+    let (task, handle) = async_task::spawn(Panic, |_| {}, ());
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| task.run())).is_err());
+    drop(handle);
+
+    assert_eq!(
+        kinds(),
+        [
+            Event::Spawn,
+            Event::PollStart,
+            Event::PollEnd(PollOutcome::Panicked),
+            Event::Destroy,
+        ]
+    );
+}
@@ -28,6 +28,7 @@ macro_rules! future {
         }
 
         let $name = {
+            #[allow(dead_code)]
             struct Fut(Box<i32>);
 
             impl Future for Fut {
@@ -45,6 +46,7 @@ macro_rules! future {
                 }
             }
 
+            #[allow(dead_code)]
             struct Out(Box<i32>);
 
             impl Drop for Out {
@@ -73,6 +75,7 @@ macro_rules! schedule {
         }
 
         let $name = {
+            #[allow(dead_code)]
             struct Guard(Box<i32>);
 
             impl Drop for Guard {
@@ -83,7 +86,7 @@ macro_rules! schedule {
 
             let guard = Guard(Box::new(0));
             move |task: Task<_>| {
-                &guard;
+                let _ = &guard;
                 task.schedule();
                 $sched.fetch_add(1);
             }
@@ -105,6 +108,7 @@ macro_rules! task {
         }
 
         let ($task, $handle) = {
+            #[allow(dead_code)]
             struct Tag(Box<i32>);
 
             impl Drop for Tag {
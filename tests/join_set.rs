@@ -0,0 +1,109 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_task::{JoinSet, Task};
+use crossbeam::channel::{unbounded, Receiver};
+use futures::channel::oneshot;
+use futures::executor::block_on;
+use futures::future::{self, FutureExt};
+
+// Runs all tasks in the queue until it is empty.
+fn run_all(queue: &Receiver<Task<()>>) {
+    while let Ok(task) = queue.try_recv() {
+        task.run();
+    }
+}
+
+#[test]
+fn completion_order() {
+    let (s, r) = unbounded();
+    let mut set = JoinSet::new();
+    let mut senders = Vec::new();
+
+    for i in 0..3 {
+        let (tx, rx) = oneshot::channel::<()>();
+        let s = s.clone();
+        let (task, handle) = async_task::spawn(
+            async move {
+                rx.await.unwrap();
+                i
+            },
+            move |t| s.send(t).unwrap(),
+            (),
+        );
+        task.schedule();
+        set.insert(handle);
+        senders.push(Some(tx));
+    }
+    run_all(&r);
+    assert_eq!(set.len(), 3);
+    assert!(set.join_next().now_or_never().is_none());
+
+    for &i in &[2, 0, 1] {
+        senders[i].take().unwrap().send(()).unwrap();
+        run_all(&r);
+        assert_eq!(block_on(set.join_next()), Some(Some(i)));
+    }
+
+    assert!(set.is_empty());
+    assert_eq!(block_on(set.join_next()), None);
+}
+
+#[test]
+fn cancel_all() {
+    let (s, r) = unbounded();
+    let mut set = JoinSet::new();
+
+    for _ in 0..3 {
+        let s = s.clone();
+        let (task, handle) =
+            async_task::spawn(future::pending::<()>(), move |t| s.send(t).unwrap(), ());
+        task.schedule();
+        set.insert(handle);
+    }
+    run_all(&r);
+
+    set.cancel_all();
+    run_all(&r);
+
+    for _ in 0..3 {
+        assert_eq!(block_on(set.join_next()), Some(None));
+    }
+    assert_eq!(block_on(set.join_next()), None);
+}
+
+#[test]
+fn drop_cancels() {
+    struct Guard(Arc<AtomicUsize>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (s, r) = unbounded();
+    let mut set = JoinSet::new();
+
+    for _ in 0..3 {
+        let guard = Guard(drops.clone());
+        let s = s.clone();
+        let (task, handle) = async_task::spawn(
+            async move {
+                let _guard = guard;
+                future::pending::<()>().await
+            },
+            move |t| s.send(t).unwrap(),
+            (),
+        );
+        task.schedule();
+        set.insert(handle);
+    }
+    run_all(&r);
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+    drop(set);
+    run_all(&r);
+    assert_eq!(drops.load(Ordering::SeqCst), 3);
+}
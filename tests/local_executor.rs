@@ -0,0 +1,142 @@
+#![cfg(feature = "executor")]
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use async_task::executor::LocalExecutor;
+use futures::channel::oneshot;
+use futures::future;
+
+#[test]
+fn spawn_non_send() {
+    let ex = LocalExecutor::new();
+    let val = Rc::new(Cell::new(0));
+
+    let handles: Vec<_> = (0..10)
+        .map(|_| {
+            let val = val.clone();
+            ex.spawn(async move { val.set(val.get() + 1) })
+        })
+        .collect();
+
+    ex.run_until(future::join_all(handles));
+    assert_eq!(val.get(), 10);
+}
+
+#[test]
+fn try_tick() {
+    let ex = LocalExecutor::new();
+    assert!(!ex.try_tick());
+
+    let handle = ex.spawn(async { 1 });
+    assert!(ex.try_tick());
+    assert!(!ex.try_tick());
+    assert_eq!(ex.run_until(handle), Some(1));
+}
+
+#[test]
+fn tick_blocks() {
+    let ex = LocalExecutor::new();
+    let (s, r) = oneshot::channel();
+
+    let handle = ex.spawn(async { r.await.unwrap() });
+    ex.tick();
+
+    // The task is woken from another thread.
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        s.send(5).unwrap();
+    });
+    ex.tick();
+
+    assert_eq!(ex.run_until(handle), Some(5));
+}
+
+#[test]
+fn run_until_foreign_wake() {
+    let ex = LocalExecutor::new();
+    let (s, r) = oneshot::channel();
+
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        s.send("hi").unwrap();
+    });
+    assert_eq!(ex.run_until(r), Ok("hi"));
+}
+
+#[test]
+fn drop_cancels() {
+    let ex = LocalExecutor::new();
+    let val = Rc::new(Cell::new(0));
+
+    let handle = ex.spawn({
+        let val = val.clone();
+        async move { val.set(1) }
+    });
+    drop(ex);
+
+    assert_eq!(val.get(), 0);
+    assert_eq!(futures::executor::block_on(handle), None);
+}
+
+/// Sets a flag when dropped.
+struct DropFlag(Arc<AtomicBool>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Spawns a task that holds a drop flag and waits for a message, and lets it start waiting.
+fn waiting_task(ex: &LocalExecutor, dropped: &Arc<AtomicBool>) -> oneshot::Sender<()> {
+    let (s, r) = oneshot::channel();
+    let flag = DropFlag(dropped.clone());
+    ex.spawn(async move {
+        let _flag = flag;
+        let _ = r.await;
+    });
+    ex.tick();
+    s
+}
+
+#[test]
+fn wake_after_drop_cancels() {
+    let ex = LocalExecutor::new();
+    let dropped = Arc::new(AtomicBool::new(false));
+    let s = waiting_task(&ex, &dropped);
+
+    drop(ex);
+    assert!(!dropped.load(Ordering::SeqCst));
+
+    // The task is woken on its own thread, so it gets canceled right away.
+    s.send(()).unwrap();
+    assert!(dropped.load(Ordering::SeqCst));
+}
+
+#[test]
+fn foreign_wake_after_drop_cancels_on_exit() {
+    let dropped = Arc::new(AtomicBool::new(false));
+
+    thread::spawn({
+        let dropped = dropped.clone();
+        move || {
+            let ex = LocalExecutor::new();
+            let s = waiting_task(&ex, &dropped);
+            drop(ex);
+
+            // The task is woken on another thread, which can't cancel it.
+            thread::spawn(move || s.send(()).unwrap()).join().unwrap();
+            assert!(!dropped.load(Ordering::SeqCst));
+        }
+    })
+    .join()
+    .unwrap();
+
+    // It was canceled when its thread exited.
+    assert!(dropped.load(Ordering::SeqCst));
+}
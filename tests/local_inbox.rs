@@ -0,0 +1,127 @@
+#![cfg(feature = "std")]
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use async_task::{LocalInbox, Task};
+use futures::channel::oneshot;
+
+thread_local! {
+    static QUEUE: RefCell<VecDeque<Task<()>>> = const { RefCell::new(VecDeque::new()) };
+}
+
+fn push(task: Task<()>) {
+    QUEUE.with(|q| q.borrow_mut().push_back(task));
+}
+
+fn run_queue() {
+    while let Some(task) = QUEUE.with(|q| q.borrow_mut().pop_front()) {
+        task.run();
+    }
+}
+
+#[test]
+fn local_wake_schedules_directly() {
+    let inbox = LocalInbox::new(push);
+    let (task, _handle) = inbox.spawn(async {}, ());
+
+    task.schedule();
+    assert!(inbox.is_empty());
+    assert_eq!(QUEUE.with(|q| q.borrow().len()), 1);
+    run_queue();
+}
+
+#[test]
+fn foreign_wake_goes_to_inbox() {
+    let notified = Arc::new(AtomicUsize::new(0));
+    let inbox = LocalInbox::with_notify(push, {
+        let notified = notified.clone();
+        move || {
+            notified.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+
+    let (s, r) = oneshot::channel();
+    let out = Rc::new(Cell::new(0));
+    let (task, _handle) = inbox.spawn(
+        {
+            let out = out.clone();
+            async move { out.set(r.await.unwrap()) }
+        },
+        (),
+    );
+    task.schedule();
+    run_queue();
+
+    thread::spawn(move || s.send(7).unwrap()).join().unwrap();
+
+    // The task is in the inbox rather than in the thread-local queue of the other thread.
+    assert_eq!(notified.load(Ordering::SeqCst), 1);
+    assert_eq!(inbox.len(), 1);
+    assert_eq!(inbox.drain(), 1);
+    run_queue();
+    assert_eq!(out.get(), 7);
+}
+
+#[test]
+fn unparks_origin_thread() {
+    let inbox = LocalInbox::new(push);
+    let (s, r) = oneshot::channel();
+    let out = Rc::new(Cell::new(0));
+
+    let (task, _handle) = inbox.spawn(
+        {
+            let out = out.clone();
+            async move { out.set(r.await.unwrap()) }
+        },
+        (),
+    );
+    task.schedule();
+
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        s.send(3).unwrap();
+    });
+
+    while out.get() == 0 {
+        inbox.drain();
+        run_queue();
+        if out.get() == 0 {
+            thread::park();
+        }
+    }
+    assert_eq!(out.get(), 3);
+}
+
+#[test]
+fn drop_cancels_inbox() {
+    let inbox = LocalInbox::new(push);
+    let (s, r) = oneshot::channel::<()>();
+    let rc = Rc::new(());
+
+    let (task, handle) = inbox.spawn(
+        {
+            let rc = rc.clone();
+            async move {
+                let _ = r.await;
+                drop(rc);
+            }
+        },
+        (),
+    );
+    task.schedule();
+    run_queue();
+
+    thread::spawn(move || s.send(()).unwrap()).join().unwrap();
+    assert_eq!(inbox.len(), 1);
+
+    // The task is canceled on this thread.
+    drop(inbox);
+    assert_eq!(Rc::strong_count(&rc), 1);
+    assert_eq!(futures::executor::block_on(handle), None);
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "metrics")]
+
+use std::panic::catch_unwind;
+use std::sync::{Mutex, MutexGuard};
+
+use async_task::Metrics;
+use futures::executor::block_on;
+use futures::future;
+
+/// Counters are global, so tests are serialized to measure exact differences.
+fn lock() -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Returns how much each counter grew since `before`, with `alive` as the current count.
+fn diff(before: Metrics) -> [usize; 6] {
+    let after = async_task::metrics();
+    [
+        after.spawned() - before.spawned(),
+        after.alive(),
+        after.completed() - before.completed(),
+        after.canceled() - before.canceled(),
+        after.panicked() - before.panicked(),
+        after.stale_wakes() - before.stale_wakes(),
+    ]
+}
+
+#[test]
+fn completed() {
+    let _lock = lock();
+    let before = async_task::metrics();
+
+    let (task, handle) = async_task::spawn(async { 7 }, |_| {}, ());
+    assert_eq!(diff(before), [1, before.alive() + 1, 0, 0, 0, 0]);
+
+    task.run();
+    assert_eq!(block_on(handle), Some(7));
+    assert_eq!(diff(before), [1, before.alive(), 1, 0, 0, 0]);
+}
+
+#[test]
+fn canceled() {
+    let _lock = lock();
+    let before = async_task::metrics();
+
+    // Canceled through the handle.
+    let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+    handle.cancel();
+    drop(task);
+    drop(handle);
+
+    // Canceled by dropping the task reference.
+    let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+    drop(task);
+    drop(handle);
+
+    // Canceled by dropping the last waker of a detached task.
+    let (task, handle) = async_task::spawn(future::pending::<()>(), |_| {}, ());
+    let waker = task.waker();
+    task.run();
+    drop(handle);
+    drop(waker);
+
+    assert_eq!(diff(before), [3, before.alive(), 0, 3, 0, 0]);
+}
+
+#[test]
+fn panicked() {
+    let _lock = lock();
+    let before = async_task::metrics();
+
+    let (task, handle) = async_task::spawn(async { panic!() }, |_| {}, ());
+    assert!(catch_unwind(|| task.run()).is_err());
+    drop(handle);
+
+    assert_eq!(diff(before), [1, before.alive(), 0, 0, 1, 0]);
+}
+
+#[test]
+fn stale_wakes() {
+    let _lock = lock();
+    let before = async_task::metrics();
+
+    let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+    let waker = task.waker();
+    task.run();
+
+    waker.wake_by_ref();
+    waker.wake();
+    drop(handle);
+
+    assert_eq!(diff(before), [1, before.alive(), 1, 0, 0, 2]);
+}
@@ -0,0 +1,114 @@
+#![cfg(feature = "std")]
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::thread;
+
+use async_task::{LocalMisuse, Mailbox};
+use crossbeam::channel;
+
+// Sets a flag when dropped.
+struct DropFlag(Arc<AtomicBool>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+// A future that completes on its second poll.
+struct Twice(bool);
+
+impl Future for Twice {
+    type Output = i32;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<i32> {
+        if self.0 {
+            Poll::Ready(7)
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+// The leaked future is never freed, which valgrind reports as a leak.
+#[test]
+#[cfg_attr(valgrind, ignore)]
+fn leak_on_foreign_poll() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let flag = DropFlag(dropped.clone());
+
+    let (task, handle) = async_task::spawn_local_with_policy(
+        async move {
+            let _flag = flag;
+        },
+        |_| {},
+        (),
+        LocalMisuse::Leak,
+    );
+
+    // Running on the wrong thread panics, and the future is leaked instead of dropped.
+    assert!(thread::spawn(move || task.run()).join().is_err());
+    assert!(!dropped.load(Ordering::SeqCst));
+    assert_eq!(futures::executor::block_on(handle), None);
+}
+
+#[test]
+fn send_back_poll() {
+    let mailbox = Mailbox::register();
+    let (s, r) = channel::unbounded();
+
+    let (task, handle) = async_task::spawn_local_with_policy(
+        Twice(false),
+        move |t| s.send(t).unwrap(),
+        (),
+        LocalMisuse::SendBack,
+    );
+
+    // Running on another thread sends the task back instead of polling it.
+    thread::spawn(move || task.run()).join().unwrap();
+    assert!(r.is_empty());
+
+    // The task is run on this thread, where it schedules itself as usual.
+    assert_eq!(mailbox.drain(), 1);
+    assert_eq!(r.len(), 1);
+    while let Ok(task) = r.try_recv() {
+        task.run();
+    }
+    assert_eq!(futures::executor::block_on(handle), Some(7));
+}
+
+#[test]
+fn send_back_drop() {
+    let mailbox = Mailbox::register();
+    let dropped = Arc::new(AtomicBool::new(false));
+    let flag = DropFlag(dropped.clone());
+
+    let (task, handle) = async_task::spawn_local_with_policy(
+        async move {
+            let _flag = flag;
+        },
+        |_| {},
+        (),
+        LocalMisuse::SendBack,
+    );
+
+    thread::spawn(move || drop(task)).join().unwrap();
+    assert!(!dropped.load(Ordering::SeqCst));
+
+    // The future is dropped on this thread.
+    assert_eq!(mailbox.drain(), 1);
+    assert!(dropped.load(Ordering::SeqCst));
+    assert_eq!(futures::executor::block_on(handle), None);
+}
+
+#[test]
+#[should_panic(expected = "no mailbox")]
+fn send_back_needs_mailbox() {
+    async_task::spawn_local_with_policy(async {}, |_| {}, (), LocalMisuse::SendBack);
+}
@@ -0,0 +1,81 @@
+use std::sync::Arc;
+use std::thread;
+
+use async_task::{Builder, LocalMisuse, TaskName};
+
+#[test]
+fn unnamed() {
+    let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+    assert_eq!(task.name(), None);
+    assert_eq!(handle.name(), None);
+    assert!(format!("{:?}", task).contains("name: None"));
+}
+
+#[test]
+fn spawn_named() {
+    let (task, handle) = async_task::spawn_named("worker", async {}, |_| {}, ());
+    assert_eq!(task.name(), Some("worker"));
+    assert_eq!(handle.name(), Some("worker"));
+
+    assert!(format!("{:?}", task).contains("name: Some(\"worker\")"));
+    assert!(format!("{:?}", handle).contains("name: Some(\"worker\")"));
+}
+
+#[test]
+fn builder_with_shared_name() {
+    let name: Arc<str> = format!("conn-{}", 7).into();
+    let (task, handle) = Builder::new().name(name).spawn(async { 1 }, |_| {}, ());
+
+    task.run();
+    assert_eq!(handle.name(), Some("conn-7"));
+    assert_eq!(futures::executor::block_on(handle), Some(1));
+}
+
+#[test]
+fn builder_spawn_local() {
+    let (task, handle) = Builder::new()
+        .name("local")
+        .spawn_local(async { 2 }, |_| {}, ());
+
+    assert_eq!(task.name(), Some("local"));
+    task.run();
+    assert_eq!(futures::executor::block_on(handle), Some(2));
+}
+
+// The leaked future is never freed, which valgrind reports as a leak.
+#[test]
+#[cfg_attr(valgrind, ignore)]
+fn builder_spawn_local_with_policy() {
+    let (task, handle) = Builder::new().name("guarded").spawn_local_with_policy(
+        async { 3 },
+        |_| {},
+        (),
+        LocalMisuse::Leak,
+    );
+    assert_eq!(handle.name(), Some("guarded"));
+
+    // The name shows up in the panic message when the task is run on the wrong thread.
+    let err = thread::spawn(move || task.run()).join().unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(msg.contains("\"guarded\""));
+    assert_eq!(futures::executor::block_on(handle), None);
+}
+
+#[test]
+fn spawn_location_of_named_task() {
+    let (task, handle) = async_task::spawn_named("located", async {}, |_| {}, ());
+    assert_eq!(task.spawn_location().file(), file!());
+    assert_eq!(task.spawn_location(), handle.spawn_location());
+}
+
+#[test]
+fn task_name() {
+    let a = TaskName::from("a");
+    let b = TaskName::from(String::from("a"));
+
+    assert_eq!(a, b);
+    assert_eq!(a, "a");
+    assert_eq!(b.as_str(), "a");
+    assert_eq!(a.to_string(), "a");
+    assert_eq!(format!("{:?}", b), "\"a\"");
+}
@@ -26,6 +26,7 @@ macro_rules! future {
         }
 
         let $name = {
+            #[allow(dead_code)]
             struct Fut(Box<i32>);
 
             impl Future for Fut {
@@ -64,6 +65,7 @@ macro_rules! schedule {
         }
 
         let $name = {
+            #[allow(dead_code)]
             struct Guard(Box<i32>);
 
             impl Drop for Guard {
@@ -74,7 +76,7 @@ macro_rules! schedule {
 
             let guard = Guard(Box::new(0));
             move |_task: Task<_>| {
-                &guard;
+                let _ = &guard;
                 $sched.fetch_add(1);
             }
         };
@@ -95,6 +97,7 @@ macro_rules! task {
         }
 
         let ($task, $handle) = {
+            #[allow(dead_code)]
             struct Tag(Box<i32>);
 
             impl Drop for Tag {
@@ -0,0 +1,115 @@
+#![cfg(feature = "executor")]
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use async_task::executor::PriorityExecutor;
+use futures::channel::oneshot;
+
+// Spawns one task per priority in `prios` that logs its priority when run.
+fn spawn_all(ex: &PriorityExecutor, prios: &[usize]) -> Arc<Mutex<Vec<usize>>> {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    for &p in prios {
+        let log = log.clone();
+        ex.spawn_with_priority(async move { log.lock().unwrap().push(p) }, p);
+    }
+    log
+}
+
+#[test]
+fn strict() {
+    let ex = PriorityExecutor::new(3);
+    let log = spawn_all(&ex, &[2, 1, 0, 2, 0, 1]);
+
+    while ex.try_tick() {}
+    assert_eq!(*log.lock().unwrap(), [0, 0, 1, 1, 2, 2]);
+}
+
+#[test]
+fn tag_is_priority() {
+    let ex = PriorityExecutor::new(3);
+    let handle = ex.spawn_with_priority(async { 1 }, 2);
+    assert_eq!(*handle.tag(), 2);
+    assert_eq!(ex.run_until(handle), Some(1));
+}
+
+#[test]
+fn weighted_fair() {
+    let ex = PriorityExecutor::builder(2).weights(vec![3, 1]).build();
+    let log = spawn_all(&ex, &[[0; 8], [1; 8]].concat());
+
+    for _ in 0..8 {
+        ex.try_tick();
+    }
+
+    // The first level gets three runs for every run of the second level.
+    let log = log.lock().unwrap();
+    assert_eq!(log.iter().filter(|&&p| p == 0).count(), 6);
+    assert_eq!(log.iter().filter(|&&p| p == 1).count(), 2);
+}
+
+#[test]
+fn aging() {
+    let ex = PriorityExecutor::builder(2).aging(3).build();
+    let log = spawn_all(&ex, &[1, 0, 0, 0, 0, 0, 0]);
+
+    while ex.try_tick() {}
+
+    // The low-priority task runs after being passed over three times.
+    assert_eq!(*log.lock().unwrap(), [0, 0, 0, 1, 0, 0, 0]);
+}
+
+#[test]
+fn run_until_foreign_wake() {
+    let ex = Arc::new(PriorityExecutor::new(2));
+    let (s, r) = oneshot::channel();
+
+    let handle = ex.spawn_with_priority(async { r.await.unwrap() }, 1);
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        s.send(3).unwrap();
+    });
+
+    assert_eq!(ex.run_until(handle), Some(3));
+}
+
+#[test]
+fn drop_cancels() {
+    let ex = PriorityExecutor::new(1);
+    let handle = ex.spawn_with_priority(async { 1 }, 0);
+    drop(ex);
+    assert_eq!(futures::executor::block_on(handle), None);
+}
+
+#[test]
+fn wake_after_drop_cancels() {
+    let ex = PriorityExecutor::new(1);
+    let (s, r) = oneshot::channel::<()>();
+    let (dropped_s, dropped_r) = oneshot::channel::<()>();
+
+    let handle = ex.spawn_with_priority(
+        async move {
+            let _dropped = dropped_s;
+            let _ = r.await;
+        },
+        0,
+    );
+    assert!(ex.try_tick());
+    drop(ex);
+
+    // The task is canceled as soon as it is woken instead of staying in the queues forever.
+    s.send(()).unwrap();
+    assert_eq!(
+        futures::executor::block_on(dropped_r),
+        Err(oneshot::Canceled)
+    );
+    assert_eq!(futures::executor::block_on(handle), None);
+}
+
+#[test]
+#[should_panic]
+fn priority_out_of_range() {
+    let ex = PriorityExecutor::new(2);
+    ex.spawn_with_priority(async {}, 2);
+}
@@ -0,0 +1,251 @@
+#![cfg(feature = "registry")]
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+
+use async_task::{Checkpoint, TaskInfo};
+use futures::channel::oneshot;
+use futures::future;
+
+/// Returns the live task tagged with `tag`, if there is one.
+///
+/// Tests run concurrently, so every test uses its own tag.
+fn find(tag: &'static str) -> Option<TaskInfo> {
+    async_task::debug_tags::<&'static str>();
+    async_task::dump()
+        .into_iter()
+        .find(|info| info.tag() == format!("{:?}", tag))
+}
+
+#[test]
+fn spawned_task_is_listed() {
+    let (task, handle) = async_task::spawn(async {}, |_| {}, "listed");
+
+    let info = find("listed").unwrap();
+    assert!(info.is_scheduled());
+    assert!(!info.is_running());
+    assert!(info.has_handle());
+    assert_eq!(info.ref_count(), 1);
+    assert_eq!(info.flags(), ["SCHEDULED", "HANDLE"]);
+
+    drop(task);
+    drop(handle);
+    assert!(find("listed").is_none());
+}
+
+#[test]
+fn completed_task_is_unlisted() {
+    let (task, handle) = async_task::spawn(async { 1 }, |_| {}, "completed");
+    task.run();
+
+    let info = find("completed").unwrap();
+    assert!(info.is_completed());
+    assert!(!info.is_scheduled());
+    assert_eq!(info.ref_count(), 0);
+
+    assert_eq!(futures::executor::block_on(handle), Some(1));
+    assert!(find("completed").is_none());
+}
+
+#[test]
+fn ids_are_unique() {
+    let (t1, h1) = async_task::spawn(async {}, |_| {}, "first");
+    let (t2, h2) = async_task::spawn(async {}, |_| {}, "second");
+
+    let first = find("first").unwrap();
+    let second = find("second").unwrap();
+    assert!(first.id() < second.id());
+
+    drop((t1, h1, t2, h2));
+}
+
+#[test]
+fn pending_task_with_waker() {
+    let (s, r) = oneshot::channel::<()>();
+    let (task, handle) = async_task::spawn(
+        async {
+            let _ = r.await;
+        },
+        |_| {},
+        "pending",
+    );
+    task.run();
+
+    // The channel holds a waker, which keeps a reference to the task.
+    let info = find("pending").unwrap();
+    assert!(!info.is_scheduled());
+    assert!(!info.is_running());
+    assert_eq!(info.ref_count(), 1);
+
+    drop(handle);
+    drop(s);
+}
+
+#[test]
+fn elapsed_in_current_state() {
+    let (s, r) = oneshot::channel::<()>();
+    let (queue_s, queue_r) = crossbeam::channel::unbounded();
+    let (task, handle) = async_task::spawn(
+        async {
+            let _ = r.await;
+        },
+        move |t| queue_s.send(t).unwrap(),
+        "elapsed",
+    );
+    task.run();
+
+    thread::sleep(Duration::from_millis(100));
+    assert!(find("elapsed").unwrap().elapsed() >= Duration::from_millis(100));
+
+    // Waking the task changes its state and resets the time.
+    s.send(()).unwrap();
+    let info = find("elapsed").unwrap();
+    assert!(info.is_scheduled());
+    assert!(info.elapsed() < Duration::from_millis(100));
+
+    queue_r.recv().unwrap().run();
+    drop(handle);
+}
+
+#[test]
+fn unregistered_tag_shows_type_name() {
+    struct Opaque;
+
+    let (task, handle) = async_task::spawn(async {}, |_| {}, Opaque);
+    let name = format!("<{}>", std::any::type_name::<Opaque>());
+    let dump = async_task::dump();
+    assert!(dump.iter().any(|info| info.tag() == name));
+
+    drop((task, handle));
+}
+
+#[test]
+fn running_task() {
+    struct Check;
+
+    impl Future for Check {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+            let info = find("running").unwrap();
+            assert!(info.is_running());
+            assert!(!info.is_scheduled());
+            Poll::Ready(())
+        }
+    }
+
+    let (task, handle) = async_task::spawn(Check, |_| {}, "running");
+    task.run();
+    drop(handle);
+}
+
+#[test]
+fn display() {
+    let (task, handle) = async_task::spawn(async {}, |_| {}, "display");
+
+    let line = find("display").unwrap().to_string();
+    assert!(line.starts_with("task "));
+    assert!(line.contains("[SCHEDULED|HANDLE] refs=1 tag=\"display\" for "));
+
+    drop((task, handle));
+}
+
+#[test]
+fn named_task() {
+    let (task, handle) = async_task::spawn_named("dumped", async {}, |_| {}, "named");
+
+    let info = find("named").unwrap();
+    assert_eq!(info.name(), Some("dumped"));
+    assert!(info.to_string().contains(" \"dumped\" [SCHEDULED|HANDLE] "));
+
+    drop((task, handle));
+}
+
+#[test]
+fn spawn_location() {
+    let line = line!() + 1;
+    let (task, handle) = async_task::spawn(async {}, |_| {}, "location");
+
+    let info = find("location").unwrap();
+    assert_eq!(info.location().file(), file!());
+    assert_eq!(info.location().line(), line);
+    assert!(info
+        .to_string()
+        .contains(&format!("spawned at {}:{}:", file!(), line)));
+
+    drop((task, handle));
+}
+
+#[test]
+fn stuck_task() {
+    let checkpoint = Checkpoint::new();
+
+    // The future never stores its waker, so nothing can wake the task again.
+    let (task, handle) = async_task::spawn(future::pending::<()>(), |_| {}, "stuck");
+    task.run();
+
+    let stuck = checkpoint.stuck();
+    assert_eq!(stuck.len(), 1);
+    assert_eq!(stuck[0].tag(), "\"stuck\"");
+    assert!(stuck[0].is_stuck());
+
+    drop(handle);
+    assert!(checkpoint.alive().is_empty());
+}
+
+#[test]
+fn waiting_task_is_not_stuck() {
+    let checkpoint = Checkpoint::new();
+
+    let (s, r) = oneshot::channel::<()>();
+    let (task, handle) = async_task::spawn(r, |_| {}, "waiting");
+    task.run();
+
+    // The channel holds the waker, so the task is alive but not stuck.
+    assert_eq!(checkpoint.alive().len(), 1);
+    assert!(checkpoint.stuck().is_empty());
+
+    drop(s);
+    drop(handle);
+}
+
+#[test]
+fn checkpoint_ignores_older_tasks() {
+    let (task, handle) = async_task::spawn(async {}, |_| {}, "older");
+    let checkpoint = Checkpoint::new();
+
+    assert!(checkpoint
+        .alive()
+        .iter()
+        .all(|info| info.tag() != "\"older\""));
+    drop((task, handle));
+}
+
+#[test]
+fn exit_report() {
+    // The report goes to standard error, so it is checked in a child process running this test.
+    if std::env::var_os("EXIT_REPORT_CHILD").is_some() {
+        let checkpoint = Checkpoint::new();
+        let (task, _handle) = async_task::spawn(future::pending::<()>(), |_| {}, "exit");
+        task.run();
+
+        // The guard is dropped before the handle.
+        let _report = checkpoint.report_at_exit();
+        return;
+    }
+
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "exit_report", "--test-threads=1"])
+        .env("EXIT_REPORT_CHILD", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("async-task: 1 task(s) still alive at exit:"));
+    assert!(stderr.contains("(stuck)"));
+    assert!(stderr.contains(file!()));
+}
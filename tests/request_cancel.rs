@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+
+use async_task::Task;
+use crossbeam::channel::{unbounded, Receiver};
+use futures::executor::block_on;
+use futures::future;
+
+// Runs all tasks in the queue until it is empty.
+fn run_all(queue: &Receiver<Task<()>>) {
+    while let Ok(task) = queue.try_recv() {
+        task.run();
+    }
+}
+
+#[test]
+fn outside_task() {
+    assert!(!async_task::cancel_requested());
+}
+
+#[test]
+fn graceful() {
+    let polls = Arc::new(AtomicUsize::new(0));
+    let (s, r) = unbounded();
+
+    let (task, handle) = async_task::spawn(
+        {
+            let polls = polls.clone();
+            future::poll_fn(move |_| {
+                polls.fetch_add(1, Ordering::SeqCst);
+                if async_task::cancel_requested() {
+                    Poll::Ready(1)
+                } else {
+                    Poll::Pending
+                }
+            })
+        },
+        move |t| s.send(t).unwrap(),
+        (),
+    );
+    task.schedule();
+    run_all(&r);
+    assert_eq!(polls.load(Ordering::SeqCst), 1);
+
+    handle.request_cancel();
+    run_all(&r);
+    assert_eq!(polls.load(Ordering::SeqCst), 2);
+    assert_eq!(block_on(handle), Some(1));
+}
+
+#[test]
+fn ignored_then_canceled() {
+    let polls = Arc::new(AtomicUsize::new(0));
+    let (s, r) = unbounded();
+
+    let (task, handle) = async_task::spawn(
+        {
+            let polls = polls.clone();
+            future::poll_fn(move |_| {
+                polls.fetch_add(1, Ordering::SeqCst);
+                Poll::<()>::Pending
+            })
+        },
+        move |t| s.send(t).unwrap(),
+        (),
+    );
+    task.schedule();
+    run_all(&r);
+
+    // The future ignores the request and keeps running.
+    handle.request_cancel();
+    run_all(&r);
+    assert_eq!(polls.load(Ordering::SeqCst), 2);
+    assert!(format!("{:?}", handle).contains("cancel_requested: true"));
+
+    handle.cancel();
+    run_all(&r);
+    assert_eq!(polls.load(Ordering::SeqCst), 2);
+    assert_eq!(block_on(handle), None);
+}
+
+#[test]
+fn after_completion() {
+    let (s, r) = unbounded();
+    let (task, handle) = async_task::spawn(future::ready(3), move |t| s.send(t).unwrap(), ());
+    task.run();
+
+    handle.request_cancel();
+    assert!(r.try_recv().is_err());
+
+    // The state of the completed task is left alone.
+    assert!(format!("{:?}", handle).contains("cancel_requested: false"));
+    assert_eq!(block_on(handle), Some(3));
+}
@@ -0,0 +1,128 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_task::{ScopedJoinHandle, Task};
+use crossbeam::channel::{unbounded, Receiver, Sender};
+use futures::executor::block_on;
+use futures::future::{self, FutureExt};
+
+// Increments a counter when dropped.
+struct Guard(Arc<AtomicUsize>);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+// Runs all tasks in the queue until it is empty.
+fn run_all(queue: &Receiver<Task<()>>) {
+    while let Ok(task) = queue.try_recv() {
+        task.run();
+    }
+}
+
+// Spawns a scoped task that never completes and spawns `depth` levels of descendants below it.
+fn spawn_tree(
+    depth: usize,
+    drops: Arc<AtomicUsize>,
+    s: Sender<Task<()>>,
+) -> (Task<()>, ScopedJoinHandle<(), ()>) {
+    let schedule = {
+        let s = s.clone();
+        move |t| s.send(t).unwrap()
+    };
+
+    async_task::spawn_scoped(
+        async move {
+            let _guard = Guard(drops.clone());
+            if depth > 0 {
+                let (task, _) = spawn_tree(depth - 1, drops, s);
+                task.schedule();
+            }
+            future::pending::<()>().await
+        },
+        schedule,
+        (),
+    )
+}
+
+#[test]
+fn cancel_subtree() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (s, r) = unbounded();
+
+    let (task, mut handle) = spawn_tree(3, drops.clone(), s);
+    task.schedule();
+    run_all(&r);
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+    handle.cancel();
+    assert!((&mut handle).now_or_never().is_none());
+
+    run_all(&r);
+    assert_eq!(drops.load(Ordering::SeqCst), 4);
+    assert_eq!(block_on(handle), None);
+}
+
+#[test]
+fn drop_task_cancels_subtree() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (s, r) = unbounded();
+
+    let (task, handle) = spawn_tree(2, drops.clone(), s);
+    task.schedule();
+    let task = r.recv().unwrap();
+    task.run();
+    run_all(&r);
+
+    // Cancel the root task once it is scheduled again by dropping its `Task`.
+    handle.waker().wake();
+    drop(r.recv().unwrap());
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+
+    run_all(&r);
+    assert_eq!(drops.load(Ordering::SeqCst), 3);
+    assert_eq!(block_on(handle), None);
+}
+
+#[test]
+fn wait_for_children() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (s, r) = unbounded();
+    let child = Arc::new(Mutex::new(None));
+
+    let (task, mut handle) = async_task::spawn_scoped(
+        {
+            let drops = drops.clone();
+            let child = child.clone();
+            let s = s.clone();
+            async move {
+                let guard = Guard(drops);
+                let (task, _) = async_task::spawn_scoped(
+                    async move {
+                        let _guard = guard;
+                        future::pending::<()>().await
+                    },
+                    move |t| s.send(t).unwrap(),
+                    (),
+                );
+                *child.lock().unwrap() = Some(task);
+                7
+            }
+        },
+        move |t| s.send(t).unwrap(),
+        (),
+    );
+    task.run();
+
+    // The parent completed, but its child has not been dropped yet.
+    assert!((&mut handle).now_or_never().is_none());
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+    // The parent canceled the child, so running it drops its future.
+    child.lock().unwrap().take().unwrap().run();
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+    assert_eq!(block_on(handle), Some(7));
+    run_all(&r);
+}
@@ -0,0 +1,178 @@
+#![cfg(feature = "sim")]
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use async_task::sim::Simulation;
+use futures::future;
+
+// A future that yields once before completing.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+fn yield_now() -> YieldNow {
+    YieldNow(false)
+}
+
+// Sets a flag when dropped.
+struct DropFlag(Arc<AtomicBool>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+// Runs a few tasks that yield a couple of times and returns the order of their steps.
+fn trace(seed: u64) -> Vec<usize> {
+    let sim = Simulation::new(seed);
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    for i in 0..5 {
+        let log = log.clone();
+        sim.spawn(async move {
+            for _ in 0..3 {
+                log.borrow_mut().push(i);
+                yield_now().await;
+            }
+        });
+    }
+    sim.run();
+
+    let log = log.borrow().clone();
+    log
+}
+
+#[test]
+fn same_seed_same_order() {
+    for seed in 0..20 {
+        assert_eq!(trace(seed), trace(seed));
+    }
+}
+
+#[test]
+fn seeds_explore_orders() {
+    let first = trace(0);
+    assert!((1..20).any(|seed| trace(seed) != first));
+}
+
+#[test]
+fn virtual_clock() {
+    let sim = Simulation::new(1);
+    let clock = sim.clock();
+
+    let handle = sim.spawn(async move {
+        clock.sleep(Duration::from_secs(3600)).await;
+        clock.sleep(Duration::from_secs(1)).await;
+        clock.now()
+    });
+
+    assert_eq!(sim.run_until(handle), Some(Duration::from_secs(3601)));
+    assert_eq!(sim.now(), Duration::from_secs(3601));
+}
+
+#[test]
+fn timeout() {
+    let sim = Simulation::new(2);
+    let clock = sim.clock();
+
+    let handle = sim.spawn({
+        let clock = clock.clone();
+        async move { clock.sleep(Duration::from_secs(10)).await }
+    });
+
+    let res = sim.run_until(handle.timeout(Duration::from_secs(5), &clock));
+    assert!(res.is_err());
+    assert_eq!(sim.now(), Duration::from_secs(5));
+}
+
+#[test]
+fn other_wakers_not_intercepted() {
+    let sim = Simulation::new(3);
+    let woken = Arc::new(AtomicBool::new(false));
+
+    let handle = sim.spawn({
+        let woken = woken.clone();
+        async move {
+            let waker = async_task::waker_fn({
+                let woken = woken.clone();
+                move || woken.store(true, Ordering::SeqCst)
+            });
+            waker.wake();
+
+            // Only wakes of the simulation's own tasks are delayed.
+            woken.load(Ordering::SeqCst)
+        }
+    });
+
+    assert_eq!(sim.run_until(handle), Some(true));
+}
+
+#[test]
+fn drop_with_pending_timer() {
+    let dropped = Arc::new(AtomicBool::new(false));
+
+    {
+        let sim = Simulation::new(5);
+        let clock = sim.clock();
+        let flag = DropFlag(dropped.clone());
+
+        sim.spawn(async move {
+            let _flag = flag;
+            clock.sleep(Duration::from_secs(1)).await;
+        });
+        assert!(sim.step());
+    }
+
+    // The task waiting for the timer is dropped along with the simulation.
+    assert!(dropped.load(Ordering::SeqCst));
+}
+
+#[test]
+fn cancel_races_completion() {
+    let mut outcomes = Vec::new();
+
+    for seed in 0..50 {
+        let sim = Simulation::new(seed);
+        let handle = sim.spawn(async {
+            yield_now().await;
+            1
+        });
+
+        let out = sim.run_until(async move {
+            yield_now().await;
+            handle.cancel();
+            handle.await
+        });
+        outcomes.push(out);
+    }
+
+    assert!(outcomes.contains(&Some(1)));
+    assert!(outcomes.contains(&None));
+}
+
+#[test]
+#[should_panic(expected = "deadlocked")]
+fn deadlock() {
+    let sim = Simulation::new(4);
+    sim.run_until(future::pending::<()>());
+}
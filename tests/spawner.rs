@@ -0,0 +1,76 @@
+use std::rc::Rc;
+
+use async_task::{JoinHandle, LocalSpawn, Spawn, Spawner};
+use crossbeam::channel;
+use futures::executor::block_on;
+
+// Library code that is generic over the executor.
+fn add<S: Spawn>(spawner: &S, a: i32, b: i32) -> JoinHandle<i32, S::Tag> {
+    spawner.spawn(async move { a + b })
+}
+
+#[test]
+fn schedule_fn() {
+    let (s, r) = channel::unbounded();
+    let spawner = Spawner::new(move |task| s.send(task).unwrap());
+
+    let handle = add(&spawner, 1, 2);
+    r.recv().unwrap().run();
+    assert_eq!(block_on(handle), Some(3));
+}
+
+#[test]
+fn tag_is_cloned() {
+    let (s, r) = channel::unbounded();
+    let spawner = Spawner::with_tag(move |task| s.send(task).unwrap(), "net");
+
+    let handle = add(&spawner, 1, 2);
+    assert_eq!(*handle.tag(), "net");
+    assert_eq!(*r.recv().unwrap().tag(), "net");
+}
+
+#[test]
+fn local() {
+    let (s, r) = channel::unbounded();
+    let spawner = Spawner::new(move |task| s.send(task).unwrap());
+
+    let val = Rc::new(5);
+    let handle = spawner.spawn_local(async move { *val });
+    r.recv().unwrap().run();
+    assert_eq!(block_on(handle), Some(5));
+}
+
+#[cfg(feature = "executor")]
+#[test]
+fn executors() {
+    use async_task::executor::{LocalExecutor, PriorityExecutor, ThreadPerCore, ThreadPool};
+
+    let pool = ThreadPool::builder().num_threads(1).build().unwrap();
+    assert_eq!(block_on(add(&pool, 1, 2)), Some(3));
+
+    let ex = PriorityExecutor::new(3);
+    let handle = add(&ex, 1, 2);
+    assert_eq!(*handle.tag(), 2);
+    assert_eq!(ex.run_until(handle), Some(3));
+
+    let ex = ThreadPerCore::new(2);
+    let cores: Vec<_> = (0..4).map(|i| *add(&ex, i, i).tag()).collect();
+    assert_eq!(cores, [0, 1, 0, 1]);
+
+    let ex = LocalExecutor::new();
+    let val = Rc::new(5);
+    let handle = ex.spawn_local(async move { *val });
+    assert_eq!(ex.run_until(handle), Some(5));
+}
+
+#[test]
+fn spawn_location() {
+    let (s, _r) = channel::unbounded();
+    let spawner = Spawner::new(move |task| s.send(task).unwrap());
+
+    // The location of the call to `spawn()`, not the one inside `Spawner`.
+    let line = line!() + 1;
+    let handle = spawner.spawn(async {});
+    assert_eq!(handle.spawn_location().file(), file!());
+    assert_eq!(handle.spawn_location().line(), line);
+}
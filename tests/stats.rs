@@ -0,0 +1,153 @@
+#![cfg(feature = "stats")]
+
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Once;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::channel::oneshot;
+
+thread_local! {
+    // The clock is global, so time is kept per thread to keep concurrent tests apart.
+    static NOW: Cell<Duration> = const { Cell::new(Duration::from_secs(0)) };
+}
+
+/// Installs a manual clock and resets the time of this thread.
+fn setup() {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| async_task::set_stats_clock(|| NOW.with(|n| n.get())).unwrap());
+    NOW.with(|n| n.set(Duration::from_secs(0)));
+}
+
+fn advance(ms: u64) {
+    NOW.with(|n| n.set(n.get() + Duration::from_millis(ms)));
+}
+
+/// A future that takes `ms` milliseconds to poll and then waits for `r`.
+struct Slow {
+    ms: u64,
+    r: oneshot::Receiver<()>,
+}
+
+impl Future for Slow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        advance(self.ms);
+        self.ms = 1;
+        Pin::new(&mut self.r).poll(cx).map(|_| ())
+    }
+}
+
+#[test]
+fn set_clock_twice() {
+    setup();
+    assert!(async_task::set_stats_clock(|| Duration::from_secs(0)).is_err());
+}
+
+#[test]
+fn new_task() {
+    setup();
+
+    let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+    assert_eq!(task.stats(), async_task::TaskStats::default());
+    assert_eq!(handle.stats(), task.stats());
+}
+
+#[test]
+fn timings() {
+    setup();
+
+    let (s, r) = oneshot::channel();
+    let (queue_s, queue_r) = crossbeam::channel::unbounded();
+    let (task, handle) =
+        async_task::spawn(Slow { ms: 3, r }, move |t| queue_s.send(t).unwrap(), ());
+
+    // Waits 5ms to be run, then polls for 3ms.
+    advance(5);
+    task.run();
+
+    // Waits 10ms to be woken, then 2ms to be run, then polls for 1ms.
+    advance(10);
+    s.send(()).unwrap();
+    advance(2);
+    let task = queue_r.recv().unwrap();
+    task.run();
+
+    let stats = handle.stats();
+    assert_eq!(stats.polls(), 2);
+    assert_eq!(stats.wakes(), 1);
+    assert_eq!(stats.total_poll_time(), Duration::from_millis(4));
+    assert_eq!(stats.max_poll_time(), Duration::from_millis(3));
+    assert_eq!(stats.total_schedule_latency(), Duration::from_millis(7));
+    assert_eq!(stats.max_schedule_latency(), Duration::from_millis(5));
+    assert_eq!(stats.idle_time(), Duration::from_millis(10));
+}
+
+#[test]
+fn idle_so_far() {
+    setup();
+
+    let (s, r) = oneshot::channel();
+    let (task, handle) = async_task::spawn(Slow { ms: 0, r }, |_| {}, ());
+    task.run();
+
+    advance(20);
+    assert_eq!(handle.stats().idle_time(), Duration::from_millis(20));
+
+    drop(s);
+    drop(handle);
+}
+
+#[test]
+fn wakes_are_counted() {
+    setup();
+
+    let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+    let waker = task.waker();
+    waker.wake_by_ref();
+    waker.wake_by_ref();
+    waker.wake();
+
+    assert_eq!(handle.stats().wakes(), 3);
+    drop(task);
+}
+
+#[test]
+fn kinds_of_wakes() {
+    setup();
+
+    let mut polls = 0;
+    let future = std::future::poll_fn(move |cx| {
+        polls += 1;
+        if polls == 1 {
+            // The first wake reschedules the task, and the second one is redundant.
+            cx.waker().wake_by_ref();
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    });
+
+    let (queue_s, queue_r) = crossbeam::channel::unbounded();
+    let (task, handle) = async_task::spawn(future, move |t| queue_s.send(t).unwrap(), ());
+    let waker = task.waker();
+    task.run();
+
+    // The task is scheduled, so this wake is redundant too.
+    waker.wake_by_ref();
+    queue_r.recv().unwrap().run();
+
+    // The task is completed, so this wake is stale.
+    waker.wake();
+
+    let stats = handle.stats();
+    assert_eq!(stats.wakes(), 4);
+    assert_eq!(stats.wakes_while_running(), 1);
+    assert_eq!(stats.redundant_wakes(), 2);
+    assert_eq!(stats.stale_wakes(), 1);
+}
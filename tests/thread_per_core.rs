@@ -0,0 +1,114 @@
+#![cfg(feature = "executor")]
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+
+use async_task::executor::ThreadPerCore;
+use futures::channel::oneshot;
+use futures::executor::block_on;
+
+fn thread_name() -> Option<String> {
+    thread::current().name().map(String::from)
+}
+
+#[test]
+fn tag_is_core() {
+    let ex = ThreadPerCore::new(3);
+    let handle = ex.spawn_on(2, async { thread_name() });
+    assert_eq!(*handle.tag(), 2);
+    assert_eq!(
+        block_on(handle),
+        Some(Some("async-task-core-2".to_string()))
+    );
+}
+
+#[test]
+fn foreign_wake_stays_on_core() {
+    let ex = ThreadPerCore::new(2);
+    let (s, r) = oneshot::channel();
+
+    let handle = ex.spawn_on(1, async move {
+        let before = thread_name();
+        r.await.unwrap();
+        (before, thread_name())
+    });
+
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        s.send(()).unwrap();
+    });
+
+    let (before, after) = block_on(handle).unwrap();
+    assert_eq!(before, after);
+    assert_eq!(after.unwrap(), "async-task-core-1");
+}
+
+#[test]
+fn local_future_woken_from_other_thread() {
+    let ex = ThreadPerCore::new(2);
+    let (s, r) = oneshot::channel();
+
+    let handle = ex.spawn_local_on(0, move || {
+        let count = Rc::new(Cell::new(1));
+        async move {
+            let n = r.await.unwrap();
+            count.set(count.get() + n);
+            count.get()
+        }
+    });
+
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        s.send(2).unwrap();
+    });
+    assert_eq!(block_on(handle), Some(3));
+}
+
+#[test]
+fn cancel_local_from_other_thread() {
+    let ex = ThreadPerCore::new(1);
+    let (_s, r) = oneshot::channel::<()>();
+
+    let handle = ex.spawn_local_on(0, move || {
+        let rc = Rc::new(());
+        async move {
+            let _ = r.await;
+            drop(rc);
+        }
+    });
+
+    // The future is dropped on its core rather than on this thread.
+    handle.cancel();
+    assert_eq!(block_on(handle), None);
+}
+
+#[test]
+fn shutdown_cancels() {
+    let ex = ThreadPerCore::new(2);
+    let (s, r) = oneshot::channel::<()>();
+
+    let handle = ex.spawn_on(0, async move { r.await.ok() });
+    ex.shutdown();
+
+    // The task is canceled when woken after shutdown.
+    drop(s);
+    assert_eq!(block_on(handle), None);
+}
+
+#[test]
+fn shutdown_from_core() {
+    let ex = ThreadPerCore::new(2);
+    let (s, r) = oneshot::channel();
+
+    let handle = ex.spawn_on(1, async move {
+        let ex: ThreadPerCore = r.await.unwrap();
+        ex.shutdown();
+        1
+    });
+    s.send(ex).unwrap();
+
+    // The core running the task doesn't wait for itself to stop.
+    assert_eq!(block_on(handle), Some(1));
+}
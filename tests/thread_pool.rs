@@ -0,0 +1,135 @@
+#![cfg(feature = "executor")]
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::thread;
+
+use async_task::executor::ThreadPool;
+use futures::channel::oneshot;
+use futures::executor::block_on;
+use futures::future;
+
+// A future that wakes itself up and yields until `stop` is set.
+struct Spin(Arc<AtomicBool>);
+
+impl Future for Spin {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0.load(Ordering::SeqCst) {
+            Poll::Ready(())
+        } else {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+#[test]
+fn spawn_many() {
+    let pool = ThreadPool::builder().num_threads(4).build().unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    let handles: Vec<_> = (0..1000)
+        .map(|_| {
+            let count = count.clone();
+            pool.spawn(async move {
+                count.fetch_add(1, Ordering::SeqCst);
+            })
+        })
+        .collect();
+
+    block_on(future::join_all(handles));
+    assert_eq!(count.load(Ordering::SeqCst), 1000);
+}
+
+#[test]
+fn thread_names() {
+    let pool = ThreadPool::builder()
+        .num_threads(1)
+        .name("pool")
+        .build()
+        .unwrap();
+    let handle = pool.spawn(async { thread::current().name().map(String::from) });
+    assert_eq!(block_on(handle), Some(Some("pool-0".to_string())));
+}
+
+#[test]
+fn yielding_is_fair() {
+    let pool = ThreadPool::builder().num_threads(1).build().unwrap();
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let spin = pool.spawn(Spin(stop.clone()));
+    let other = pool.spawn({
+        let stop = stop.clone();
+        async move { stop.store(true, Ordering::SeqCst) }
+    });
+
+    block_on(other);
+    block_on(spin);
+}
+
+#[test]
+fn panic_is_caught() {
+    let pool = ThreadPool::builder().num_threads(1).build().unwrap();
+
+    let handle = pool.spawn(async { panic!("boom") });
+    assert_eq!(block_on(handle), None::<()>);
+
+    let handle = pool.spawn(async { 1 });
+    assert_eq!(block_on(handle), Some(1));
+}
+
+#[test]
+fn shutdown_cancels() {
+    let pool = ThreadPool::builder().num_threads(2).build().unwrap();
+    let (s, r) = oneshot::channel::<()>();
+
+    let handle = pool.spawn(async move { r.await.ok() });
+    pool.shutdown();
+
+    // The task is canceled when woken after shutdown.
+    drop(s);
+    assert_eq!(block_on(handle), None);
+}
+
+#[test]
+fn wake_during_shutdown_cancels() {
+    for _ in 0..100 {
+        let pool = ThreadPool::builder().num_threads(1).build().unwrap();
+        let (s, r) = oneshot::channel::<()>();
+        let (dropped_s, dropped_r) = oneshot::channel::<()>();
+
+        pool.spawn(async move {
+            let _dropped = dropped_s;
+            r.await.ok()
+        });
+
+        // Race the wake against the shutdown.
+        let waker = thread::spawn(move || drop(s));
+        pool.shutdown();
+        waker.join().unwrap();
+
+        // The task is canceled either way.
+        assert_eq!(block_on(dropped_r), Err(oneshot::Canceled));
+    }
+}
+
+#[test]
+fn shutdown_from_worker() {
+    let pool = ThreadPool::builder().num_threads(2).build().unwrap();
+    let (s, r) = oneshot::channel();
+
+    let handle = pool.spawn(async move {
+        let pool: ThreadPool = r.await.unwrap();
+        pool.shutdown();
+        1
+    });
+    s.send(pool).unwrap();
+
+    // The worker running the task doesn't wait for itself to stop.
+    assert_eq!(block_on(handle), Some(1));
+}
@@ -0,0 +1,115 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use async_task::{Task, Timer};
+use futures::executor::block_on;
+use futures::future::{self, FutureExt};
+
+// A timer that fires only when told to.
+#[derive(Clone, Default)]
+struct ManualTimer(Arc<AtomicBool>);
+
+impl ManualTimer {
+    fn fire(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+struct Sleep(Arc<AtomicBool>);
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        if self.0.load(Ordering::SeqCst) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Timer for ManualTimer {
+    type Instant = ();
+    type Sleep = Sleep;
+
+    fn sleep(&self, _duration: Duration) -> Sleep {
+        Sleep(self.0.clone())
+    }
+
+    fn sleep_until(&self, _deadline: ()) -> Sleep {
+        Sleep(self.0.clone())
+    }
+}
+
+// A future that stays pending and counts how many times it was dropped.
+struct Pending(Arc<AtomicUsize>);
+
+impl Future for Pending {
+    type Output = i32;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<i32> {
+        Poll::Pending
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn noop_schedule(_: Task<()>) {}
+
+#[test]
+fn completes_in_time() {
+    let timer = ManualTimer::default();
+    let (task, handle) = async_task::spawn(future::ready(7), noop_schedule, ());
+    task.run();
+
+    timer.fire();
+    let res = block_on(handle.timeout(Duration::from_secs(1), &timer));
+    assert_eq!(res, Ok(Some(7)));
+}
+
+#[test]
+fn elapsed_keeps_running() {
+    let timer = ManualTimer::default();
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (task, handle) = async_task::spawn(Pending(drops.clone()), noop_schedule, ());
+
+    let mut timeout = handle.with_deadline((), &timer);
+    assert!((&mut timeout).now_or_never().is_none());
+
+    timer.fire();
+    assert!(block_on(&mut timeout).is_err());
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+    // The task was not canceled so it can still be polled.
+    let handle = timeout.into_inner();
+    assert!(!task.run());
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+    drop(handle);
+}
+
+#[test]
+fn elapsed_cancels() {
+    let timer = ManualTimer::default();
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (task, handle) = async_task::spawn(Pending(drops.clone()), noop_schedule, ());
+
+    timer.fire();
+    let timeout = handle
+        .timeout(Duration::from_secs(1), &timer)
+        .cancel_on_elapsed(true);
+    assert!(block_on(timeout).is_err());
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+    // The task was canceled so running it only drops the future.
+    task.run();
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+}
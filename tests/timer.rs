@@ -0,0 +1,201 @@
+#![cfg(feature = "timer")]
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use async_task::timer::Clock;
+use async_task::{block_on, Task};
+use crossbeam::channel::{self, Sender};
+use futures::future;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+// Polls a future once with a no-op waker.
+fn poll_once<F: Future + Unpin>(f: &mut F) -> Poll<F::Output> {
+    let waker = async_task::waker_fn(|| {});
+    Pin::new(f).poll(&mut Context::from_waker(&waker))
+}
+
+// Spawns a future whose task is pushed into a queue when woken.
+fn spawn<F>(future: F, queue: &Sender<Task<()>>) -> async_task::JoinHandle<(), ()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let queue = queue.clone();
+    let (task, handle) = async_task::spawn(future, move |t| queue.send(t).unwrap(), ());
+    task.run();
+    handle
+}
+
+#[test]
+fn sleep_manual() {
+    let clock = Clock::manual();
+    let mut sleep = clock.sleep(ms(100));
+
+    assert!(poll_once(&mut sleep).is_pending());
+    clock.advance(ms(99));
+    assert!(poll_once(&mut sleep).is_pending());
+    clock.advance(ms(1));
+    assert!(poll_once(&mut sleep).is_ready());
+    assert_eq!(clock.now(), ms(100));
+}
+
+#[test]
+fn fires_in_order() {
+    let clock = Clock::manual();
+    let (s, r) = channel::unbounded();
+    let log = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    // Deadlines spread across several levels of the wheel.
+    let deadlines = [5_000_000, 1, 63, 64, 65, 4095, 4096, 300_000, 70, 2];
+    let handles: Vec<_> = deadlines
+        .iter()
+        .map(|&d| {
+            let clock = clock.clone();
+            let log = log.clone();
+            spawn(
+                async move {
+                    clock.sleep(ms(d)).await;
+                    log.lock().unwrap().push((d, clock.now()));
+                },
+                &s,
+            )
+        })
+        .collect();
+
+    while let Some(deadline) = clock.next_deadline() {
+        clock.advance(deadline - clock.now());
+        while let Ok(task) = r.try_recv() {
+            task.run();
+        }
+    }
+
+    let mut sorted = deadlines.to_vec();
+    sorted.sort();
+    let expected: Vec<_> = sorted.iter().map(|&d| (d, ms(d))).collect();
+    assert_eq!(*log.lock().unwrap(), expected);
+    block_on(future::join_all(handles));
+}
+
+#[test]
+fn large_advance() {
+    let clock = Clock::manual();
+    let mut short = clock.sleep(ms(10));
+    let mut long = clock.sleep(Duration::from_secs(86_400 * 365 * 3000));
+
+    assert!(poll_once(&mut short).is_pending());
+    assert!(poll_once(&mut long).is_pending());
+
+    clock.advance(Duration::from_secs(86_400));
+    assert!(poll_once(&mut short).is_ready());
+    assert!(poll_once(&mut long).is_pending());
+}
+
+#[test]
+fn large_sleep_after_advance() {
+    let clock = Clock::manual();
+    clock.advance(ms(1));
+
+    let mut long = clock.sleep(Duration::from_secs(86_400 * 365 * 3));
+    assert!(poll_once(&mut long).is_pending());
+    assert!(clock.next_deadline().unwrap() > ms(1));
+
+    clock.advance(ms(1));
+    assert!(poll_once(&mut long).is_pending());
+
+    clock.advance(Duration::from_secs(86_400 * 365 * 3));
+    assert!(poll_once(&mut long).is_ready());
+}
+
+#[test]
+fn drop_removes_timer() {
+    let clock = Clock::manual();
+    let mut sleep = clock.sleep(ms(10));
+
+    assert!(poll_once(&mut sleep).is_pending());
+    assert_eq!(clock.next_deadline(), Some(ms(10)));
+    drop(sleep);
+    assert_eq!(clock.next_deadline(), None);
+}
+
+#[test]
+fn next_deadline_skips_removed() {
+    let clock = Clock::manual();
+    let mut sleeps: Vec<_> = [300, 100, 5000, 200]
+        .iter()
+        .map(|&t| clock.sleep(ms(t)))
+        .collect();
+    for sleep in &mut sleeps {
+        assert!(poll_once(sleep).is_pending());
+    }
+    assert_eq!(clock.next_deadline(), Some(ms(100)));
+
+    // Timers in higher levels report their exact deadline.
+    drop(sleeps.remove(1));
+    assert_eq!(clock.next_deadline(), Some(ms(200)));
+    drop(sleeps.remove(2));
+    drop(sleeps.remove(0));
+    assert_eq!(clock.next_deadline(), Some(ms(5000)));
+    sleeps.clear();
+    assert_eq!(clock.next_deadline(), None);
+}
+
+#[test]
+fn interval() {
+    let clock = Clock::manual();
+    let mut interval = clock.interval(ms(10));
+
+    assert_eq!(poll_once(&mut interval.tick()), Poll::Ready(ms(0)));
+    for i in 1..4 {
+        assert!(poll_once(&mut interval.tick()).is_pending());
+        clock.advance(ms(9));
+        assert!(poll_once(&mut interval.tick()).is_pending());
+        clock.advance(ms(1));
+        assert_eq!(poll_once(&mut interval.tick()), Poll::Ready(ms(10 * i)));
+    }
+}
+
+#[test]
+fn interval_catches_up() {
+    let clock = Clock::manual();
+    let mut interval = clock.interval(ms(10));
+
+    clock.advance(ms(35));
+    let ticks: Vec<_> = (0..5).map(|_| poll_once(&mut interval.tick())).collect();
+    assert_eq!(
+        ticks,
+        [
+            Poll::Ready(ms(0)),
+            Poll::Ready(ms(10)),
+            Poll::Ready(ms(20)),
+            Poll::Ready(ms(30)),
+            Poll::Pending,
+        ]
+    );
+}
+
+#[test]
+fn timeout() {
+    let clock = Clock::manual();
+    let (s, _r) = channel::unbounded();
+    let handle = spawn(future::pending(), &s);
+
+    let mut timeout = handle.timeout(ms(50), &clock);
+    assert!(poll_once(&mut timeout).is_pending());
+    clock.advance(ms(50));
+    assert!(poll_once(&mut timeout).is_ready());
+}
+
+#[test]
+fn sleep_real() {
+    let clock = Clock::real();
+    let start = Instant::now();
+
+    block_on(clock.sleep(ms(50)));
+    assert!(start.elapsed() >= ms(50));
+    assert!(clock.now() >= ms(50));
+}
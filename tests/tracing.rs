@@ -0,0 +1,244 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures::channel::oneshot;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// A subscriber that records spans and events as lines of text.
+#[derive(Clone, Default)]
+struct Recorder {
+    next: Arc<AtomicU64>,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Recorder {
+    fn push(&self, line: String) {
+        self.log.lock().unwrap().push(line);
+    }
+
+    fn log(&self) -> Vec<String> {
+        self.log.lock().unwrap().clone()
+    }
+}
+
+/// Collects the fields of a span or event.
+struct Fields(String);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0 += &format!(" {}={:?}", field.name(), value);
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let id = Id::from_u64(self.next.fetch_add(1, Ordering::SeqCst) + 1);
+        let mut fields = Fields(String::new());
+        attrs.record(&mut fields);
+        self.push(format!(
+            "new {} {}{} root={}",
+            id.into_u64(),
+            attrs.metadata().name(),
+            fields.0,
+            attrs.is_root()
+        ));
+        id
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields(String::new());
+        event.record(&mut fields);
+        let parent = event.parent().map(|id| id.into_u64());
+        self.push(format!("event{} parent={:?}", fields.0, parent));
+    }
+
+    fn enter(&self, id: &Id) {
+        self.push(format!("enter {}", id.into_u64()));
+    }
+
+    fn exit(&self, id: &Id) {
+        self.push(format!("exit {}", id.into_u64()));
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        self.push(format!("close {}", id.into_u64()));
+        true
+    }
+}
+
+/// Replaces task IDs, which depend on other tests, with `N`.
+fn normalize(log: Vec<String>, task_id: u64) -> Vec<String> {
+    log.into_iter()
+        .map(|line| line.replace(&format!("task.id={}", task_id), "task.id=N"))
+        .collect()
+}
+
+/// Extracts the task ID from the first line of the log.
+fn task_id(log: &[String]) -> u64 {
+    let start = log[0].find("task.id=").unwrap() + "task.id=".len();
+    let end = log[0][start..].find(' ').unwrap() + start;
+    log[0][start..end].parse().unwrap()
+}
+
+#[test]
+fn span_per_task() {
+    let recorder = Recorder::default();
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let (s, r) = oneshot::channel::<()>();
+        let (queue_s, queue_r) = crossbeam::channel::unbounded();
+        let (task, handle) = async_task::spawn(
+            async {
+                let _ = r.await;
+            },
+            move |t| queue_s.send(t).unwrap(),
+            (),
+        );
+        task.run();
+        s.send(()).unwrap();
+        queue_r.recv().unwrap().run();
+        drop(handle);
+    });
+
+    let log = recorder.log();
+    let id = task_id(&log);
+    assert_eq!(
+        normalize(log, id),
+        [
+            "new 1 task task.id=N root=true",
+            "enter 1",
+            "exit 1",
+            "event message=task woken task.id=N parent=Some(1)",
+            "enter 1",
+            "exit 1",
+            "event message=task completed task.id=N parent=Some(1)",
+            "close 1",
+        ]
+    );
+}
+
+#[test]
+fn complete_without_handle() {
+    let recorder = Recorder::default();
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+        drop(handle);
+
+        // Running destroys the task, which must happen after its span is exited.
+        task.run();
+    });
+
+    let log = recorder.log();
+    let id = task_id(&log);
+    assert_eq!(
+        normalize(log, id),
+        [
+            "new 1 task task.id=N root=true",
+            "enter 1",
+            "exit 1",
+            "event message=task completed task.id=N parent=Some(1)",
+            "close 1",
+        ]
+    );
+}
+
+#[test]
+fn cancel_event() {
+    let recorder = Recorder::default();
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+        handle.cancel();
+        drop(task);
+        drop(handle);
+    });
+
+    let log = recorder.log();
+    let id = task_id(&log);
+    assert_eq!(
+        normalize(log, id),
+        [
+            "new 1 task task.id=N root=true",
+            "event message=task canceled task.id=N parent=Some(1)",
+            "close 1",
+        ]
+    );
+}
+
+#[test]
+fn spawned_inside_span_is_root() {
+    let recorder = Recorder::default();
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let outer = tracing::info_span!("outer");
+        let _enter = outer.enter();
+
+        let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+        drop((task, handle));
+    });
+
+    let log = recorder.log();
+    assert!(log[2].starts_with("new 2 task "));
+    assert!(log[2].ends_with(" root=true"));
+}
+
+#[test]
+fn named_span() {
+    let recorder = Recorder::default();
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let (task, handle) = async_task::spawn_named("worker", async {}, |_| {}, ());
+        drop((task, handle));
+    });
+
+    let log = recorder.log();
+    let id = task_id(&log);
+    assert_eq!(
+        normalize(log, id)[0],
+        "new 1 task task.id=N task.name=\"worker\" root=true"
+    );
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn wake_storm_warning() {
+    let recorder = Recorder::default();
+    async_task::set_wake_storm_threshold(3);
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        // The task is scheduled until it runs, so every wake is redundant.
+        let (task, handle) = async_task::spawn(async {}, |_| {}, ());
+        for _ in 0..5 {
+            task.waker().wake_by_ref();
+        }
+        drop((task, handle));
+    });
+
+    let log = recorder.log();
+    let id = task_id(&log);
+    let warnings: Vec<String> = normalize(log, id)
+        .into_iter()
+        .filter(|line| line.contains("already scheduled"))
+        .collect();
+    assert_eq!(
+        warnings,
+        [concat!(
+            "event message=task woken 3 times while already scheduled ",
+            "task.id=N task.redundant_wakes=3 parent=Some(1)"
+        )]
+    );
+}
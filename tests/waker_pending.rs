@@ -29,6 +29,7 @@ macro_rules! future {
         }
 
         let ($name, $waker) = {
+            #[allow(dead_code)]
             struct Fut(Box<i32>);
 
             impl Future for Fut {
@@ -72,6 +73,7 @@ macro_rules! schedule {
         let ($name, $chan) = {
             let (s, r) = channel::unbounded();
 
+            #[allow(dead_code)]
             struct Guard(Box<i32>);
 
             impl Drop for Guard {
@@ -82,7 +84,7 @@ macro_rules! schedule {
 
             let guard = Guard(Box::new(0));
             let sched = move |task: Task<_>| {
-                &guard;
+                let _ = &guard;
                 $sched.fetch_add(1);
                 s.send(task).unwrap();
             };
@@ -106,6 +108,7 @@ macro_rules! task {
         }
 
         let ($task, $handle) = {
+            #[allow(dead_code)]
             struct Tag(Box<i32>);
 
             impl Drop for Tag {
@@ -30,6 +30,7 @@ macro_rules! future {
         }
 
         let ($name, $waker) = {
+            #[allow(dead_code)]
             struct Fut(Cell<bool>, Box<i32>);
 
             impl Future for Fut {
@@ -81,6 +82,7 @@ macro_rules! schedule {
         let ($name, $chan) = {
             let (s, r) = channel::unbounded();
 
+            #[allow(dead_code)]
             struct Guard(Box<i32>);
 
             impl Drop for Guard {
@@ -91,7 +93,7 @@ macro_rules! schedule {
 
             let guard = Guard(Box::new(0));
             let sched = move |task: Task<_>| {
-                &guard;
+                let _ = &guard;
                 $sched.fetch_add(1);
                 s.send(task).unwrap();
             };
@@ -115,6 +117,7 @@ macro_rules! task {
         }
 
         let ($task, $handle) = {
+            #[allow(dead_code)]
             struct Tag(Box<i32>);
 
             impl Drop for Tag {
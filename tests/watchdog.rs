@@ -0,0 +1,110 @@
+#![cfg(feature = "watchdog")]
+
+use std::thread;
+use std::time::Duration;
+
+use async_task::{SlowPoll, Watchdog};
+use crossbeam::channel::{self, Receiver};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Starts a watchdog that sends reports into a channel.
+fn watchdog(threshold: Duration) -> (Watchdog, Receiver<SlowPoll>) {
+    async_task::debug_tags::<&'static str>();
+
+    let (s, r) = channel::unbounded();
+    let watchdog = Watchdog::new(threshold, move |report| {
+        let _ = s.send(report);
+    });
+    (watchdog, r)
+}
+
+/// Runs a task tagged with `tag` that blocks for `duration` on a thread named `name`.
+fn block(name: &str, tag: &'static str, duration: Duration) {
+    thread::Builder::new()
+        .name(name.into())
+        .spawn(move || {
+            let future = async move { thread::sleep(duration) };
+            let (task, handle) = async_task::spawn(future, |_| {}, tag);
+            task.run();
+            drop(handle);
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+/// Returns the reports about the task tagged with `tag`.
+///
+/// Watchdogs see the tasks of all tests, so reports are filtered by tag.
+fn reports(r: &Receiver<SlowPoll>, tag: &str) -> Vec<SlowPoll> {
+    let tag = format!("{:?}", tag);
+    r.try_iter().filter(|report| report.tag() == tag).collect()
+}
+
+#[test]
+fn blocking_poll() {
+    let (watchdog, r) = watchdog(ms(50));
+    block("blocker", "blocking", ms(300));
+
+    let reports: Vec<SlowPoll> = reports(&r, "blocking")
+        .into_iter()
+        .filter(|report| !report.is_finished())
+        .collect();
+    assert_eq!(reports.len(), 1);
+    assert!(reports[0].backtrace().is_none());
+    assert_eq!(reports[0].thread_name(), Some("blocker"));
+    assert!(reports[0].elapsed() >= ms(50));
+    assert!(reports[0]
+        .to_string()
+        .contains("blocked thread \"blocker\""));
+
+    drop(watchdog);
+}
+
+#[test]
+fn fast_polls() {
+    let (watchdog, r) = watchdog(ms(200));
+    for _ in 0..10 {
+        block("fast", "fast", ms(1));
+    }
+    thread::sleep(ms(100));
+
+    assert!(reports(&r, "fast").is_empty());
+    drop(watchdog);
+}
+
+#[test]
+fn report_while_blocked() {
+    let (watchdog, r) = watchdog(ms(20));
+
+    let blocker = thread::spawn(|| block("stuck", "stuck", ms(1000)));
+
+    // The report arrives before the poll ends.
+    let report = r.iter().find(|report| report.tag() == "\"stuck\"").unwrap();
+    assert_eq!(report.thread_name(), Some("stuck"));
+    assert!(!blocker.is_finished());
+
+    blocker.join().unwrap();
+    drop(watchdog);
+}
+
+#[test]
+fn follow_up_with_backtrace() {
+    let (watchdog, r) = watchdog(ms(20));
+    block("follow-up", "follow-up", ms(200));
+
+    // The follow-up arrives after the poll has finished.
+    let report = r
+        .iter()
+        .find(|report| report.tag() == "\"follow-up\"" && report.is_finished())
+        .unwrap();
+    assert_eq!(report.thread_name(), Some("follow-up"));
+    assert!(report.elapsed() >= ms(200));
+    assert!(report.backtrace().is_some());
+    assert!(report.to_string().ends_with(" (finished)"));
+
+    drop(watchdog);
+}